@@ -6,6 +6,7 @@ import { qs, event } from "../Utils/DOMhelpers";
 
 import jsonData from "../Data/data.json" assert { type: "json" };
 import init, { main, set_config } from "../../wasm/pkg/wasm";
+import { API_VERSION, ApiReply, SimError } from "../Utils/api.types";
 
 const output = qs(".output");
 
@@ -24,10 +25,27 @@ export const global = {
  */
 function formatSimQuery(query: SimQuery): string {
     return JSON.stringify({
+        "api_version": API_VERSION,
         "type": query.queryType,
         "data": query
     })
 }
+/**
+ * Formats an error replied by the wasm module to a message for the user
+ * @param err SimError
+ * @returns Error message
+ */
+function formatApiError(err: SimError): string {
+    switch (err.code) {
+      case "unknown_strategy":
+        return `Unknown strategy ${err.strat} for ${err.theory}`;
+      case "unsupported_version":
+        return `API version ${err.requested} is not supported, the sim uses version ${err.supported}`;
+      default:
+        return err.message;
+    }
+}
+
 // Removing this any is a bit annoying, will do it later -Mathis
 function formatSimResponse(response: any): SimResponse {
     return {
@@ -51,9 +69,9 @@ async function simCall() {
     const query = parseQuery();
     const APIresponse = main(formatSimQuery(query));
     console.log(APIresponse);
-    const parsed_response: API_response = JSON.parse(APIresponse)
+    const parsed_response: ApiReply = JSON.parse(APIresponse)
     if (parsed_response.response_type == "failure") {
-      throw parsed_response.data
+      throw formatApiError(parsed_response.data)
     }
     else {
       writeSimResponse(formatSimResponse(parsed_response.data));
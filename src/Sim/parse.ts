@@ -147,7 +147,7 @@ function parseStepChainSim(): StepChainQuery {
         rho: parseCurrency(currencyInput.value, theory, sigma),
         cap: parseCurrency(capInput.value, theory, sigma),
         step: parseExponentialValue(modeInput.value),
-        hardCap: hardCap.checked,
+        hard_cap: hardCap.checked,
         settings: parseSettings()
     }
 }
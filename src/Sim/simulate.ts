@@ -229,7 +229,7 @@ async function stepChainSim(query: StepChainQuery): Promise<StepSimResponse> {
             theory: query.theory,
             strat: query.strat,
             cap: query.cap,
-            hard_cap: query.hardCap
+            hard_cap: query.hard_cap
         }, false);
         if (!global.simulating) break;
 
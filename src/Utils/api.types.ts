@@ -0,0 +1,842 @@
+/*
+ * Types of the API of the wasm module, generated from its JSON Schema. Do not edit.
+ */
+
+export const API_VERSION = 1;
+
+/** Strategy category played during a daily time window */
+export type ActivityWindow = {
+  /** Strategy category (Very-Active, Active, Semi-Idle, Idle) */
+  category: string;
+  /** End of the window (seconds since midnight), the window wraps around midnight if it is before `start` */
+  end: number;
+  /** Start of the window (seconds since midnight) */
+  start: number;
+};
+
+/** Query sent to the wasm module */
+export type ApiQuery = {
+  /** Version of the API the query is written for, the current version if missing */
+  api_version?: number | null;
+  /** Overrides of data.json for this query only, as a JSON merge patch */
+  config?: unknown;
+} & ({
+  data: SingleSimQuery;
+  type: "single";
+} | {
+  data: ResumeQuery;
+  type: "resume";
+} | {
+  data: ChainSimQuery;
+  type: "chain";
+} | {
+  data: StepSimQuery;
+  type: "step";
+} | {
+  data: StepChainQuery;
+  type: "step_chain";
+} | {
+  data: SimAllQuery;
+  type: "all";
+} | {
+  data: PlanQuery;
+  type: "plan";
+} | {
+  data: ConvergenceQuery;
+  type: "convergence";
+} | {
+  data: RzZerosQuery;
+  type: "zeros";
+} | {
+  data: TuneQuery;
+  type: "tune";
+} | {
+  data: CrossoverQuery;
+  type: "crossover";
+} | {
+  data: CompareQuery;
+  type: "compare";
+} | {
+  data: FilterSweepQuery;
+  type: "filter_sweep";
+} | {
+  data: PubTableQuery;
+  type: "pub_table";
+} | {
+  data: DescribeQuery;
+  type: "describe";
+} | {
+  data: ConvertQuery;
+  type: "convert";
+} | {
+  data: unknown[];
+  type: "batch";
+});
+
+/** Response of the wasm module */
+export type ApiReply = {
+  /** Version of the API of the module */
+  api_version: number;
+} & ({
+  data: SimResponse;
+  response_type: "success";
+} | {
+  data: SimError;
+  response_type: "failure";
+});
+
+export type ApiResponse = {
+  data: SimResponse;
+  response_type: "success";
+} | {
+  data: SimError;
+  response_type: "failure";
+};
+
+export type ChainSimQuery = {
+  cap?: RawLogNum | null;
+  /** Publications only happen when the player checks in */
+  check_ins?: CheckInSchedule | null;
+  custom_strat?: CustomStratQuery | null;
+  /**
+   * * Strategy category of each time of the day, overriding `strat`
+   *      *
+   *      * Publications started while the player is offline use the idle category with autobuy.
+   */
+  daily_profile?: DailyProfile | null;
+  hard_cap: boolean;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  strat: string;
+  /** The chain stops once tau is multiplied by this target */
+  tau_target?: LogNum | null;
+  theory: TheoryType;
+  /** The chain stops after this time (seconds), the last publication may be partial */
+  time_budget?: number | null;
+};
+
+export type ChainSimResponse = {
+  average_rate: number;
+  delta_tau: LogNum;
+  /**
+   * * Time needed to reach the tau target, None without tau target
+   *      *
+   *      * If the chain stopped before the target (time budget or cap), the time of the missing tau is
+   *      * extrapolated from `average_rate`.
+   */
+  eta: number | null;
+  results: SimResult[];
+  total_time: number;
+};
+
+/**
+ * * Times at which the player opens the game, repeating every `period`
+ *  *
+ *  * Checking in every 8 hours is `{"times": [0], "period": 28800}`, a weekly calendar lists the check-ins
+ *  * of a week with a period of 604800 seconds.
+ */
+export type CheckInSchedule = {
+  /** If true, variables are also only bought at check-ins */
+  buy_at_check_ins?: boolean;
+  /** Time of the period (seconds) at which the simulation starts */
+  offset?: number;
+  /** Length of the period (seconds) */
+  period: number;
+  /** Check-in times (seconds) relative to the start of the period */
+  times: number[];
+};
+
+/** Result of a strategy compared to the reference strategy */
+export type CompareEntry = {
+  /** None if both purchase lists are identical, or for unaligned rows */
+  divergence: PurchaseDivergence | null;
+  result: SimResult;
+  /** tau/h of this strategy divided by the tau/h of the reference, None for unaligned rows */
+  tau_h_ratio: number | null;
+  /** Publication time of this strategy minus the one of the reference, None for unaligned rows */
+  time_delta: number | null;
+};
+
+export type CompareQuery = {
+  cap: RawLogNum;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  /** Step between the compared publications, the strategies are chained from `rho` to `cap` without being compared if None */
+  step?: RawLogNum | null;
+  /** Strategies (or strategy categories) compared, the first one is the reference */
+  strats: string[];
+  theory: TheoryType;
+};
+
+export type CompareResponse = {
+  /** Chain mode only: average tau gain rate of each chain divided by the one of the first strategy */
+  rate_ratio: number[] | null;
+  rows: CompareRow[];
+  strats: string[];
+  /** Chain mode only: total time of each chain minus the one of the first strategy */
+  time_delta: number[] | null;
+  /** Total time of each strategy */
+  total_time: number[];
+};
+
+/**
+ * * Results of the strategies for the same step, or the n-th publication of each chain
+ *  *
+ *  * Rows are aligned when all their entries start at the same rho: every row of a step comparison,
+ *  * and the first row of chains. Entries are None once a chain has ended.
+ */
+export type CompareRow = {
+  entries: (CompareEntry | null)[];
+  /**
+   * * rho at which every entry starts, None for unaligned rows
+   *      *
+   *      * The entries of unaligned rows start at the `last_pub` of their result.
+   */
+  rho: LogNum | null;
+};
+
+/** Possible user configurations for how to sim completed CTs */
+export type CompletedCTs = "in" | "end" | "no";
+
+/** Result of a sim with given tick settings, compared to the exact game tick */
+export type ConvergenceEntry = {
+  result: SimResult;
+  /** Publication rho error (log10) */
+  rho_error: number;
+  /** Relative tau/h error */
+  tau_h_error: number;
+  tick_settings: TickSettings;
+  /** Number of ticks simulated, used as the cost of the settings */
+  ticks: number;
+  /** Relative publication time error */
+  time_error: number;
+};
+
+export type ConvergenceQuery = {
+  /** Tick settings compared to the exact game tick, a default ladder is used if None */
+  ladder?: TickSettings[] | null;
+  last_strat?: string | null;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  strat: string;
+  theory: TheoryType;
+  /** Relative tau/h error accepted for the recommended settings */
+  tolerance?: number | null;
+};
+
+export type ConvergenceResponse = {
+  /** Entries in the order of the ladder */
+  entries: ConvergenceEntry[];
+  /** Cheapest settings within the tolerance, the exact game tick if no settings of the ladder are accurate enough */
+  recommended: TickSettings;
+  /** Result with the exact game tick (dt = 0.15, ddt = 1) */
+  reference: SimResult;
+};
+
+export type ConvertQuery = {
+  sigma: number;
+  theory: TheoryType;
+  unit: ValueUnit;
+  value: LogNum;
+};
+
+/** Last publication of a theory, as rho, tau and total multiplier */
+export type ConvertResponse = {
+  multiplier: LogNum;
+  rho: LogNum;
+  tau: LogNum;
+  theory: TheoryType;
+};
+
+/** Description of a cost model and its parameters */
+export type CostModel = {
+  cutoff: number;
+  model1: CostModel;
+  model2: CostModel;
+  type: "composite";
+} | {
+  base: LogNum;
+  coefficient: LogNum;
+  type: "exponential";
+} | {
+  model: CostModel;
+  step: number;
+  type: "stepwise";
+} | {
+  cost: LogNum;
+  type: "constant";
+} | {
+  model: CostModel;
+  type: "first_free";
+};
+
+/** Point where the best of the two strategies changes */
+export type CrossoverPoint = {
+  above: CrossoverSide;
+  below: CrossoverSide;
+  rho: LogNum;
+};
+
+export type CrossoverQuery = {
+  cap: RawLogNum;
+  /** Width (log10 of rho) under which a crossover is considered located */
+  precision?: number | null;
+  /** rho interval searched, sampled like a step sim (at most 1000 rho values) before refining */
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  step: RawLogNum;
+  /** The two strategies (or strategy categories) compared */
+  strats: string[];
+  theory: TheoryType;
+};
+
+export type CrossoverResponse = {
+  crossovers: CrossoverPoint[];
+  strats: string[];
+};
+
+/** tau/h of both strategies at a given rho */
+export type CrossoverSide = {
+  rho: LogNum;
+  tau_h: number[];
+};
+
+/** Custom strategy, as buying conditions by variable name */
+export type CustomStratQuery = {
+  conditions: { [key: string]: string };
+  name?: string | null;
+  /** Values of the parameters used in the conditions */
+  params?: { [key: string]: number };
+};
+
+/**
+ * * Daily activity of the player
+ *  *
+ *  * The player is offline outside of the windows.
+ */
+export type DailyProfile = {
+  /** Time of the day (seconds since midnight) at which the simulation starts */
+  offset?: number;
+  windows: ActivityWindow[];
+};
+
+export type DescribeQuery = {
+  /** Last publication at which the milestone maxima are computed, the last milestone unlock by default */
+  rho?: RawLogNum | null;
+  /** Theory described, every theory if None */
+  theory?: TheoryType | null;
+};
+
+export type DescribeResponse = {
+  /** Version tag of the config in use, None for the embedded data.json */
+  config_version: string | null;
+  strat_categories: string[];
+  theories: TheoryDescription[];
+};
+
+export type FilterSweepQuery = {
+  /**
+   * * Strategies considered in each strategy category
+   *      *
+   *      * Categories missing from the map, or all of them if None, use the strategies whose current
+   *      * filter condition holds somewhere in the range.
+   */
+  candidates?: { [key: string]: string[] } | null;
+  cap: RawLogNum;
+  /** rho range swept, sampled like a step sim; grid points times candidates is at most 2000 */
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  step: RawLogNum;
+  theory: TheoryType;
+  /** Relative tau/h loss under which a strategy is considered as good as the best one */
+  tolerance?: number | null;
+};
+
+export type FilterSweepResponse = {
+  conditions: StratFilterDiff[];
+  theory: TheoryType;
+};
+
+/** Contents of `RZgoodzeros.json` */
+export type GoodZeros = {
+  genericZeros: number[];
+  longZeros: number[];
+  rzIdleBHBoundaries: ZeroBoundary[];
+  rzRewind: number[];
+  rzRewindBoundaries: ZeroBoundary[];
+  rzSpecificZeros: number[];
+  rzdIdleBoundaries: ZeroBoundary[];
+  rzdSpecificZeros: number[];
+};
+
+/** log10 of the value */
+export type LogNum = number;
+
+/** Range searched for a parameter of a tuned strategy */
+export type ParamRange = {
+  /**
+   * * Value the tuned parameters are compared against, and from which the search starts
+   *      *
+   *      * Defaults to the value of the built-in weight or of the custom strategy parameter, or to the
+   *      * middle of the range.
+   */
+  default?: number | null;
+  max: number;
+  min: number;
+};
+
+export type PlanQuery = {
+  /** Strategy category of each time of the day, overriding `strat` */
+  daily_profile?: DailyProfile | null;
+  /** Time (seconds) covered by the plan */
+  horizon: number;
+  settings: SimSettings;
+  sigma: number;
+  /** Strategy category used when there is no daily profile */
+  strat: string;
+  /** Last publication of each theory, like `SimAllQuery` */
+  values: RawValues;
+};
+
+export type PlanResponse = {
+  steps: PlanStep[];
+  total_tau: number;
+  total_time: number;
+  /** Updated last publication of each theory, in the order of the query values */
+  values: LogNum[];
+};
+
+/** Consecutive publications of the same theory in a rotation plan */
+export type PlanStep = {
+  /** Sum of the tau gains (log10) of the plan at the end of the step */
+  cumulative_tau: number;
+  cumulative_time: number;
+  pubs: number;
+  results: SimResult[];
+  /** Time (seconds) since the start of the plan at which the step starts */
+  start_time: number;
+  /** Strategy category of the publications */
+  strat: string;
+  /** Tau gain of the step (log10) */
+  tau_gain: number;
+  theory: TheoryType;
+  time: number;
+};
+
+/** Entry of a publication table whose next publication changed */
+export type PubTableChange = {
+  /** Last publication (log10) */
+  key: number;
+  new_next: number;
+  new_time: number;
+  old_next: number | null;
+  old_time: number | null;
+};
+
+export type PubTableQuery = {
+  /**
+   * * Range of last publications (log10) regenerated, the whole table by default
+   *      *
+   *      * At most 200 entries can be regenerated by one query, so the table is regenerated in ranges.
+   */
+  from?: number | null;
+  settings: SimSettings;
+  sigma: number;
+  /** Strategy used to reach the next publications */
+  strat: string;
+  /** Only BaP is supported, the sims of CSR2, EF and FP are not ported */
+  theory: TheoryType;
+  to?: number | null;
+};
+
+export type PubTableResponse = {
+  changes: PubTableChange[];
+  /** Regenerated table, in the schema of the committed one */
+  table: unknown;
+  theory: TheoryType;
+};
+
+/** First purchase differing between a strategy and the reference strategy */
+export type PurchaseDivergence = {
+  /** Index of the purchase in both purchase lists */
+  index: number;
+  /** Purchase of the compared strategy, None if its list ended */
+  other: VarBuy | null;
+  /** Purchase of the reference strategy, None if its list ended */
+  reference: VarBuy | null;
+};
+
+/** Zero of zeta ranked against the zeros before it */
+export type RankedZero = {
+  /** Modulus of the derivative of zeta at the zero */
+  deriv: number;
+  /** `deriv` divided by the largest derivative of the zeros before it */
+  ratio: number;
+  /** Position of the zero on the critical line */
+  t: number;
+};
+
+export type RawLogNum = LogNum | string;
+
+export type RawValues = RawLogNum[] | string;
+
+export type ResumeQuery = {
+  /** rho of the last publication */
+  last_pub: RawLogNum;
+  last_strat?: string | null;
+  settings: SimSettings;
+  sigma: number;
+  state: ResumeState;
+  strat: string;
+  theory: TheoryType;
+};
+
+export type ResumeResponse = {
+  /** Time left until the publication (seconds), 0 if the publication should happen now */
+  remaining_time: number;
+  /** Best publication, its purchase list only contains the remaining purchases */
+  result: SimResult;
+};
+
+/** Live in-game state of a publication in progress */
+export type ResumeState = {
+  /** Secondary currencies of the theory (q1, r...) */
+  currencies?: { [key: string]: LogNum };
+  /** Variable levels, by variable name */
+  levels?: { [key: string]: number };
+  /** Highest rho reached since the publication, defaults to `rho` */
+  max_rho?: LogNum | null;
+  /** Milestone allocation, kept until a new milestone is unlocked */
+  milestones?: number[] | null;
+  rho: LogNum;
+  /** Time since the publication (seconds) */
+  t: number;
+};
+
+export type RzZerosQuery = {
+  /** Start of the range of t searched, at least 1 */
+  from: number;
+  /** Minimum ratio of the derivative of a zero to the best derivative before it, 1 if None */
+  min_ratio?: number | null;
+  /** Grid points per average zero spacing used to bracket zeros, 8 if None and at most 64 */
+  resolution?: number | null;
+  /** End of the range of t searched */
+  to: number;
+};
+
+export type RzZerosResponse = {
+  table: GoodZeros;
+  zeros: RankedZero[];
+};
+
+export type SimAllQuery = {
+  semi_idle: boolean;
+  settings: SimSettings;
+  sigma: number;
+  values: RawValues;
+  very_active: boolean;
+};
+
+export type SimAllResponse = {
+  completed_cts: CompletedCTs;
+  results: SimAllResult[];
+  sigma: number;
+  strat_type: SimAllStrats;
+};
+
+/** Holds a result for a SimAll simulation for one theory */
+export type SimAllResult = {
+  active: SimResult;
+  /** True for custom theories whose last publication reached the maximum tau */
+  completed: boolean;
+  idle: SimResult;
+  last_pub: LogNum;
+  /** Total multiplier of the theory at the last publication */
+  multiplier: LogNum;
+  ratio: number;
+  theory: TheoryType;
+};
+
+/** Possible user configurations for which strategies to simulate in "All" mode */
+export type SimAllStrats = "all" | "active" | "idle";
+
+export type SimError = {
+  code: "parse";
+  message: string;
+} | {
+  code: "config";
+  message: string;
+} | {
+  code: "unknown_strategy";
+  strat: string;
+  theory: TheoryType;
+} | {
+  code: "invalid_query";
+  message: string;
+} | {
+  code: "numeric";
+  message: string;
+} | {
+  code: "limit_exceeded";
+  message: string;
+} | {
+  code: "unsupported_version";
+  requested: number;
+  supported: number;
+} | {
+  code: "internal";
+  message: string;
+};
+
+export type SimResponse = {
+  data: SingleSimResponse;
+  type: "single";
+} | {
+  data: ResumeResponse;
+  type: "resume";
+} | {
+  data: ChainSimResponse;
+  type: "chain";
+} | {
+  data: StepSimResponse;
+  type: "step";
+} | {
+  data: SimAllResponse;
+  type: "all";
+} | {
+  data: PlanResponse;
+  type: "plan";
+} | {
+  data: ConvergenceResponse;
+  type: "convergence";
+} | {
+  data: RzZerosResponse;
+  type: "zeros";
+} | {
+  data: TuneResponse;
+  type: "tune";
+} | {
+  data: CrossoverResponse;
+  type: "crossover";
+} | {
+  data: CompareResponse;
+  type: "compare";
+} | {
+  data: FilterSweepResponse;
+  type: "filter_sweep";
+} | {
+  data: PubTableResponse;
+  type: "pub_table";
+} | {
+  data: DescribeResponse;
+  type: "describe";
+} | {
+  data: ConvertResponse;
+  type: "convert";
+} | {
+  data: ApiResponse[];
+  type: "batch";
+};
+
+/** Holds a result of a single simulation */
+export type SimResult = {
+  bought_vars: VarBuy[];
+  delta_tau: LogNum;
+  last_pub: LogNum;
+  pub_multi: number;
+  pub_rho: LogNum;
+  sigma: number;
+  strat: string;
+  tau_h: number;
+  theory: TheoryType;
+  /** Number of ticks simulated, including the ones after the publication */
+  ticks: number;
+  time: number;
+};
+
+/** Holds sim settings */
+export type SimSettings = {
+  bought_vars_delta: number;
+  completed_cts: CompletedCTs;
+  ddt: number;
+  dt: number;
+  mf_reset_depth: number;
+  /** Shows the a2 and a3 levels in the strategies of EF, left to the EF sim */
+  show_a23: boolean;
+  show_unofficials: boolean;
+  sim_all_strats: SimAllStrats;
+};
+
+export type SingleSimQuery = {
+  /** Buys every available variable, like the in-game autobuy */
+  autobuy?: boolean;
+  cap?: RawLogNum | null;
+  /** Publications only happen when the player checks in */
+  check_ins?: CheckInSchedule | null;
+  custom_strat?: CustomStratQuery | null;
+  last_strat?: string | null;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  strat: string;
+  theory: TheoryType;
+  /** Time (seconds) after which the publication happens, if reached before the cap */
+  time_cap?: number | null;
+};
+
+export type SingleSimResponse = {
+  result: SimResult;
+};
+
+/** Chain sims started at every step of a rho range, like a step sim */
+export type StepChainQuery = {
+  cap: RawLogNum;
+  custom_strat?: CustomStratQuery | null;
+  hard_cap: boolean;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  step: RawLogNum;
+  strat: string;
+  theory: TheoryType;
+};
+
+export type StepSimQuery = {
+  cap: RawLogNum;
+  custom_strat?: CustomStratQuery | null;
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  step: RawLogNum;
+  strat: string;
+  theory: TheoryType;
+};
+
+export type StepSimResponse = {
+  results: SimResult[];
+};
+
+/** Strategy of a theory as configured in data.json */
+export type StratDescription = {
+  filter_condition: string;
+  forced_condition: string | null;
+  name: string;
+  ui_visible: boolean;
+};
+
+/** Filter condition of a strategy proposed by a sweep */
+export type StratFilterDiff = {
+  /** True if both conditions disagree somewhere in the swept range */
+  changed: boolean;
+  current: string;
+  proposed: string;
+  strat: string;
+};
+
+export type TheoryDescription = {
+  /** None if the theory is not supported by the sim yet */
+  model: TheoryModel | null;
+  strats: StratDescription[];
+  tau_factor: number;
+  theory: TheoryType;
+  ui_visible: boolean;
+  unofficial: boolean;
+};
+
+/** Publication, milestones and variables of a theory sim */
+export type TheoryModel = {
+  /** Steps of rho (log10) at which milestones are unlocked */
+  milestone_unlock_steps: number | null;
+  /** Milestone unlock points (log10 of rho), unused if `milestone_unlock_steps` is set */
+  milestone_unlocks: number[];
+  milestones_max: number[];
+  /** rho (log10) at which publications are unlocked */
+  pub_unlock: number;
+  variables: VariableDescription[];
+};
+
+/** enum of all available theory types */
+export type TheoryType = "T1" | "T2" | "T3" | "T4" | "T5" | "T6" | "T7" | "T8" | "WSP" | "SL" | "EF" | "CSR2" | "FI" | "FP" | "RZ" | "MF" | "BaP" | "BT" | "TC";
+
+/** Tick settings of a sim */
+export type TickSettings = {
+  ddt: number;
+  dt: number;
+};
+
+export type TuneQuery = {
+  cap: RawLogNum;
+  /** Strategy whose parameters are tuned, the built-in weights of `strat` are tuned if None */
+  custom_strat?: CustomStratQuery | null;
+  /** Maximum number of parameter sets evaluated */
+  max_evals?: number | null;
+  params: { [key: string]: ParamRange };
+  /** rho range in which the strategy is tuned, sampled like a step sim */
+  rho: RawLogNum;
+  settings: SimSettings;
+  sigma: number;
+  step: RawLogNum;
+  /** Strategy on which the custom strategy is applied */
+  strat: string;
+  theory: TheoryType;
+};
+
+export type TuneResponse = {
+  /** Average tau/h with the default parameters */
+  default_tau_h: number;
+  /** Number of parameter sets evaluated */
+  evaluations: number;
+  /** Relative tau/h gain of the best parameters over the default ones, None if the default tau/h is 0 */
+  gain: number | null;
+  /** Best parameters found */
+  params: { [key: string]: number };
+  /** Results with the best parameters */
+  results: SimResult[];
+  /** Average tau/h with the best parameters */
+  tau_h: number;
+};
+
+/** Description of a value model and its parameters */
+export type ValueModel = {
+  base: LogNum;
+  length: number;
+  offset: LogNum;
+  type: "stepwise";
+} | {
+  base: LogNum;
+  type: "exponential";
+} | {
+  offset: LogNum;
+  slope: LogNum;
+  type: "linear";
+};
+
+/** Unit of a value converted by a convert query */
+export type ValueUnit = "rho" | "tau" | "multiplier";
+
+/** Holds a record of a variable purchase */
+export type VarBuy = {
+  cost: LogNum;
+  level: number;
+  symbol: string;
+  timestamp: number;
+  var_name: string;
+};
+
+export type VariableDescription = {
+  cost: CostModel;
+  name: string;
+  value: ValueModel;
+};
+
+/** Range of t covered by a set of zeros for a given rho */
+export type ZeroBoundary = {
+  from: number;
+  to: number;
+  toRho: number;
+};
@@ -69,7 +69,7 @@ declare global {
     rho: number
     cap: number
     step: number
-    hardCap: boolean
+    hard_cap: boolean
   }
 
   type SimQuery = SingleSimQuery | ChainSimQuery | StepSimQuery | SimAllQuery | StepChainQuery;
@@ -158,9 +158,4 @@ declare global {
     idle: simResult;
   }
 
-  type API_response = {
-    response_type: "success" | "failure",
-    // Removing this any is a bit annoying, will do it later -Mathis
-    data: any
-  };
 }
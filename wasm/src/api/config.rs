@@ -1,10 +1,19 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use num_enum::TryFromPrimitive;
 
+use crate::api::error::SimError;
+
 /** enum of all available theory types */
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema, TryFromPrimitive)]
 #[repr(usize)]
 //#[serde(rename_all = "lowercase")]
 pub enum TheoryType {
@@ -25,14 +34,28 @@ pub enum TheoryType {
     RZ,
     MF,
     BaP,
-    TC,
-    BT
+    BT,
+    TC
+}
+
+/** configuration for a strategy of a theory */
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigStrat {
+    pub strat_filter_condition: String,
+    pub forced_condition: Option<String>,
+    #[serde(rename = "UI_visible")]
+    pub ui_visible: Option<bool>
 }
 
 /** configuration for a theory */
 #[derive(Debug, Deserialize)]
 pub struct ConfigTheories {
-    pub tau_factor: f64
+    pub tau_factor: f64,
+    #[serde(rename = "UI_visible")]
+    pub ui_visible: Option<bool>,
+    /** strategies of the theory, in the order of data.json */
+    pub strats: IndexMap<String, ConfigStrat>
 }
 
 /** structure for the given theory configuration as written in data.json needed
@@ -42,4 +65,172 @@ pub struct ConfigTheories {
 pub struct Config {
     pub theories: HashMap<TheoryType, ConfigTheories>,
     pub strat_categories: Vec<String>
+}
+
+/** data.json of the UI, vendored in wasm/data, used until a config is set */
+const DEFAULT_CONFIG_JSON: &str = include_str!("../../data/data.json");
+
+/** Configuration used by the sims, with the data.json it was parsed from */
+#[derive(Debug)]
+pub struct LoadedConfig {
+    /** Unique id of the config, sims of different configs never share results */
+    pub id: u64,
+    /** Version tag given when the config was set */
+    pub version: Option<String>,
+    /** data.json of the config, overrides are applied on it */
+    raw: Value,
+    config: Config
+}
+
+impl Deref for LoadedConfig {
+    type Target = Config;
+
+    fn deref(&self) -> &Config {
+        &self.config
+    }
+}
+
+static NEXT_CONFIG_ID: AtomicU64 = AtomicU64::new(0);
+/** Config set with `set_config`, None until then */
+static CONFIG: RwLock<Option<Arc<LoadedConfig>>> = RwLock::new(None);
+/** Embedded data.json, parsed on first use */
+static DEFAULT_CONFIG: OnceLock<Result<Arc<LoadedConfig>, SimError>> = OnceLock::new();
+
+thread_local! {
+    /** Config of the running query when it carries overrides */
+    static CONFIG_OVERRIDE: RefCell<Option<Arc<LoadedConfig>>> = const { RefCell::new(None) };
+}
+
+impl LoadedConfig {
+    fn parse(raw: Value, version: Option<String>) -> Result<Self, SimError> {
+        let config = Config::deserialize(&raw)
+            .map_err(|err| SimError::parse(format!("Error parsing config: {err}")))?;
+        Ok(LoadedConfig { id: NEXT_CONFIG_ID.fetch_add(1, Ordering::Relaxed), version, raw, config })
+    }
+
+    /** Returns this config with the JSON merge patch `overrides` applied */
+    fn with_overrides(&self, overrides: &Value) -> Result<Self, SimError> {
+        let mut raw = self.raw.clone();
+        merge_patch(&mut raw, overrides);
+        LoadedConfig::parse(raw, self.version.clone())
+            .map_err(|err| err.context("Invalid config overrides"))
+    }
+}
+
+/** Applies a JSON merge patch (RFC 7386): objects are merged and null removes a key */
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target) = target else { return };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/** Returns the config of the running query: its overrides, else the config set, else the embedded data.json */
+pub fn current_config() -> Result<Arc<LoadedConfig>, SimError> {
+    if let Some(config) = CONFIG_OVERRIDE.with_borrow(Option::clone) {
+        return Ok(config);
+    }
+    if let Some(config) = CONFIG.read().unwrap_or_else(PoisonError::into_inner).clone() {
+        return Ok(config);
+    }
+    DEFAULT_CONFIG
+        .get_or_init(|| {
+            let raw = serde_json::from_str(DEFAULT_CONFIG_JSON)
+                .map_err(|err| SimError::config(format!("Invalid embedded config: {err}")))?;
+            LoadedConfig::parse(raw, None).map(Arc::new)
+        })
+        .clone()
+}
+
+/** Replaces the config used by the sims, returns the config that was replaced */
+pub fn set_config(config: &str, version: Option<String>) -> Result<Arc<LoadedConfig>, SimError> {
+    let raw = serde_json::from_str(config)
+        .map_err(|err| SimError::parse(format!("Error parsing config: {err}")))?;
+    let config = Arc::new(LoadedConfig::parse(raw, version)?);
+    let previous = CONFIG.write().unwrap_or_else(PoisonError::into_inner).replace(config);
+    match previous {
+        Some(previous) => Ok(previous),
+        None => current_config()
+    }
+}
+
+/** Restores the overrides of the enclosing query when dropped, even if the query panicked */
+struct OverrideGuard(Option<Arc<LoadedConfig>>);
+
+impl Drop for OverrideGuard {
+    fn drop(&mut self) {
+        CONFIG_OVERRIDE.set(self.0.take());
+    }
+}
+
+/** Runs `f` with the JSON merge patch `overrides` applied on top of the current config */
+pub fn with_config_overrides<T>(overrides: Option<&Value>, f: impl FnOnce() -> Result<T, SimError>) -> Result<T, SimError> {
+    let Some(overrides) = overrides else { return f() };
+    let config = Arc::new(current_config()?.with_overrides(overrides)?);
+    let _guard = OverrideGuard(CONFIG_OVERRIDE.replace(Some(config)));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /** Copies of the UI data vendored in wasm/data, so that the crate builds on its own */
+    const VENDORED_DATA: [(&str, &str); 3] = [
+        ("data.json", "../src/Data/data.json"),
+        ("BaPpubtable.json", "../src/Theories/CTs/helpers/BaPpubtable.json"),
+        ("RZgoodzeros.json", "../src/Theories/CTs/helpers/RZgoodzeros.json")
+    ];
+
+    /** The vendored data is refreshed with `UPDATE_VENDORED_DATA=1 cargo test vendored_data` */
+    #[test]
+    fn vendored_data() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        for (vendored, ui) in VENDORED_DATA {
+            // The UI is not part of the crate, the check is skipped without it
+            let Ok(ui_data) = std::fs::read_to_string(dir.join(ui)) else { continue };
+            let vendored = dir.join("data").join(vendored);
+            if std::env::var_os("UPDATE_VENDORED_DATA").is_some() {
+                std::fs::write(&vendored, ui_data).unwrap();
+                continue;
+            }
+            let outdated = format!("{} is outdated, refresh it with UPDATE_VENDORED_DATA=1 cargo test vendored_data", vendored.display());
+            assert!(std::fs::read_to_string(&vendored).expect(&outdated) == ui_data, "{outdated}");
+        }
+    }
+
+    #[test]
+    fn merge_patches() {
+        let mut target = json!({ "a": { "b": 1, "c": 2 }, "d": [1, 2] });
+        merge_patch(&mut target, &json!({ "a": { "b": 3, "c": null }, "d": [3], "e": "f" }));
+        assert_eq!(target, json!({ "a": { "b": 3 }, "d": [3], "e": "f" }));
+    }
+
+    #[test]
+    fn query_overrides() {
+        let tau_factor = || current_config().unwrap().theories[&TheoryType::BT].tau_factor;
+        let default = tau_factor();
+        let overrides = json!({ "theories": { "BT": { "tau_factor": 0.5 } } });
+        let res = with_config_overrides(Some(&overrides), || {
+            let nested = with_config_overrides(Some(&json!({ "strat_categories": [] })), || Ok(current_config()?.strat_categories.len()));
+            Ok((tau_factor(), nested))
+        });
+        assert_eq!(res, Ok((0.5, Ok(0))));
+        assert_eq!(tau_factor(), default);
+
+        let invalid = with_config_overrides(Some(&json!({ "theories": { "BT": { "tau_factor": "x" } } })), || Ok(()));
+        assert!(matches!(invalid, Err(SimError::Parse { message }) if message.starts_with("Invalid config overrides")));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+/*!
+ * Errors that can be replied by the wasm module
+ *
+ * Errors are serialized with a code, so that the UI can handle them without parsing their message.
+ */
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::api::config::TheoryType;
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum SimError {
+    /** The query or the config could not be parsed */
+    Parse { message: String },
+    /** The config is invalid or does not cover the query */
+    Config { message: String },
+    /** The strategy or strategy category does not exist for the theory */
+    UnknownStrategy { theory: TheoryType, strat: String },
+    /** The query is well formed but its values are invalid */
+    InvalidQuery { message: String },
+    /** A computation gave a value that cannot be represented */
+    Numeric { message: String },
+    /** The query asks for more work than the module allows */
+    LimitExceeded { message: String },
+    /** The query is written for another version of the API */
+    UnsupportedVersion { requested: u32, supported: u32 },
+    /** The module panicked while handling the query */
+    Internal { message: String }
+}
+
+impl SimError {
+    pub fn parse(message: impl Into<String>) -> Self {
+        SimError::Parse { message: message.into() }
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        SimError::Config { message: message.into() }
+    }
+
+    pub fn unknown_strategy(theory: TheoryType, strat: impl Into<String>) -> Self {
+        SimError::UnknownStrategy { theory, strat: strat.into() }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        SimError::InvalidQuery { message: message.into() }
+    }
+
+    pub fn numeric(message: impl Into<String>) -> Self {
+        SimError::Numeric { message: message.into() }
+    }
+
+    pub fn limit(message: impl Into<String>) -> Self {
+        SimError::LimitExceeded { message: message.into() }
+    }
+
+    /** Error returned when the config has no entry for `theory` */
+    pub fn no_theory_config(theory: TheoryType) -> Self {
+        SimError::config(format!("No configuration found for theory {theory:?}"))
+    }
+
+    /** Returns the same error with `context` prepended to its message */
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        match self {
+            SimError::Parse { message } => SimError::parse(format!("{context}: {message}")),
+            SimError::Config { message } => SimError::config(format!("{context}: {message}")),
+            SimError::InvalidQuery { message } => SimError::invalid(format!("{context}: {message}")),
+            SimError::Numeric { message } => SimError::numeric(format!("{context}: {message}")),
+            SimError::LimitExceeded { message } => SimError::limit(format!("{context}: {message}")),
+            SimError::Internal { message } => SimError::Internal { message: format!("{context}: {message}") },
+            err @ (SimError::UnknownStrategy { .. } | SimError::UnsupportedVersion { .. }) => err
+        }
+    }
+
+    /** Creates the error of a caught panic */
+    pub fn from_panic(payload: Box<dyn std::any::Any + Send>) -> Self {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Unknown panic".to_owned());
+        SimError::Internal { message }
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::UnknownStrategy { theory, strat } => write!(f, "Unknown strategy {strat} for {theory:?}"),
+            SimError::UnsupportedVersion { requested, supported } => write!(f, "API version {requested} is not supported, the module uses version {supported}"),
+            SimError::Parse { message }
+            | SimError::Config { message }
+            | SimError::InvalidQuery { message }
+            | SimError::Numeric { message }
+            | SimError::LimitExceeded { message }
+            | SimError::Internal { message } => f.write_str(message)
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/**
+ * Runs `f`, converting a panic into an internal error
+ *
+ * Panics can only be caught where unwinding is enabled (native builds and tests), the module
+ * aborts on panics otherwise, so the sim code must still avoid them.
+ */
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, SimError>) -> Result<T, SimError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(SimError::from_panic(payload)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialized_with_codes() {
+        let err = serde_json::to_value(SimError::unknown_strategy(TheoryType::BT, "BTx")).unwrap();
+        assert_eq!(err, serde_json::json!({ "code": "unknown_strategy", "theory": "BT", "strat": "BTx" }));
+        let err = serde_json::to_value(SimError::limit("Too many evaluations")).unwrap();
+        assert_eq!(err, serde_json::json!({ "code": "limit_exceeded", "message": "Too many evaluations" }));
+    }
+
+    #[test]
+    fn panic_messages() {
+        let res: Result<(), SimError> = catch_panic(|| panic!("boom {}", 1));
+        assert_eq!(res, Err(SimError::Internal { message: "boom 1".to_owned() }));
+        assert_eq!(catch_panic(|| Ok(1)), Ok(1));
+    }
+}
@@ -0,0 +1,276 @@
+/*!
+ * Parsing of raw user inputs (currency values and sim all distributions)
+ *
+ * Queries can carry user strings instead of log10 values. They are resolved against the theory
+ * and sigma of the query before the query is deserialized.
+ */
+
+use serde_json::Value;
+
+use crate::api::{config::{TheoryType, current_config}, error::SimError, query::SimQuery};
+use crate::theories::multiplier::MultiplierModel;
+use crate::utils::lognum::LogNum;
+
+/** Fields of the queries holding a rho value */
+const CURRENCY_FIELDS: [&str; 3] = ["rho", "cap", "last_pub"];
+
+/**
+ * Parses a value written as `<number>`, `e<number>` or `<mantissa>e<exponent>`
+ *
+ * Returns the log10 of the value, `<number>` being already a log10 value.
+ */
+pub fn parse_exponential_value(s: &str) -> Result<f64, SimError> {
+    let invalid = || SimError::parse(format!("Invalid currency value {s}. Currency value must be in formats <number>, <exxxx> or <xexxxx>."));
+    let is_decimal = |s: &str| {
+        let (int, frac) = s.split_once('.').unwrap_or((s, "0"));
+        !int.is_empty() && !frac.is_empty() && int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    };
+
+    let log = s.strip_prefix('e').unwrap_or(s);
+    if is_decimal(log) {
+        return log.parse().map_err(|_| invalid());
+    }
+    match s.split_once('e') {
+        Some((mantissa, exponent)) if is_decimal(mantissa) && !exponent.is_empty() && exponent.chars().all(|c| c.is_ascii_digit()) => {
+            let mantissa: f64 = mantissa.parse().map_err(|_| invalid())?;
+            let exponent: f64 = exponent.parse().map_err(|_| invalid())?;
+            Ok(exponent + mantissa.max(1.).log10())
+        },
+        _ => Err(invalid())
+    }
+}
+
+fn tau_factor(theory: TheoryType) -> Result<f64, SimError> {
+    Ok(current_config()?
+        .theories.get(&theory)
+        .ok_or_else(|| SimError::no_theory_config(theory))?
+        .tau_factor)
+}
+
+/** Returns the rho (log10) of `theory` giving the publication multiplier `value` (log10) */
+pub fn reverse_multi(theory: TheoryType, value: f64, sigma: i64) -> Result<f64, SimError> {
+    Ok(MultiplierModel::of(theory).rho(value, tau_factor(theory)?, sigma))
+}
+
+/**
+ * Parses a currency value of `theory`
+ *
+ * The value can end with `r` (rho), `t` (tau) or `m` (publication multiplier), `default_type` is
+ * used otherwise.
+ */
+pub fn parse_currency(s: &str, theory: TheoryType, sigma: i64, default_type: char) -> Result<LogNum, SimError> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let (value, value_type) = match s.chars().last() {
+        Some(c @ ('r' | 't' | 'm')) => (&s[..s.len() - 1], c),
+        _ => (s.as_str(), default_type)
+    };
+    let value = parse_exponential_value(value).map_err(|err| err.context(format_args!("{theory:?}")))?;
+
+    let rho = match value_type {
+        't' => value / tau_factor(theory)?,
+        'm' => reverse_multi(theory, value, sigma)?,
+        _ => value
+    };
+    Ok(LogNum::from_log10(rho))
+}
+
+/** Parses a sigma value, which must be a non-negative integer */
+fn parse_sigma(s: &str) -> Result<i64, SimError> {
+    s.parse::<u32>()
+        .map(i64::from)
+        .map_err(|_| SimError::parse("Invalid sigma value. Sigma must be an integer that's >= 0"))
+}
+
+/**
+ * Parses a sim all distribution: `<sigma> <t1> [t2] ...`
+ *
+ * Theory values are tau values by default. Returns sigma and the last publication of each theory.
+ */
+pub fn parse_sim_all(s: &str) -> Result<(i64, Vec<LogNum>), SimError> {
+    let mut split = s.split_whitespace();
+    let sigma = split.next().unwrap_or("");
+    let values: Vec<&str> = split.collect();
+    if values.is_empty() {
+        return Err(SimError::parse("Student count and at least one theory value that is not 0 is required."));
+    }
+    let sigma = parse_sigma(sigma)?;
+
+    let values = values.iter().enumerate()
+        .map(|(i, value)| {
+            let theory = TheoryType::try_from(i)
+                .map_err(|_| SimError::parse(format!("Invalid value {value} does not match any theory.")))?;
+            parse_currency(value, theory, sigma, 't')
+        })
+        .collect::<Result<Vec<_>, SimError>>()?;
+    if values.iter().all(|value| value.log10_f64() <= 0.) {
+        return Err(SimError::parse("Student count and at least one theory value that is not 0 is required."));
+    }
+    Ok((sigma, values))
+}
+
+/**
+ * Schema of the query fields resolved by `parse_query`: a log10 value, or a raw user input
+ *
+ * Used with `#[schemars(with = "RawLogNum")]` on `rho`, `cap`, `last_pub` and `step`.
+ */
+pub struct RawLogNum;
+
+impl schemars::JsonSchema for RawLogNum {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "RawLogNum".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "anyOf": [
+                generator.subschema_for::<LogNum>(),
+                { "type": "string", "description": "Raw user input: e100, 1.5e100, or a currency value ending with r, t or m" }
+            ]
+        })
+    }
+}
+
+/** Schema of the `values` of sim all and plan queries: a value per theory, or a sim all distribution */
+pub struct RawValues;
+
+impl schemars::JsonSchema for RawValues {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "RawValues".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "anyOf": [
+                { "type": "array", "items": generator.subschema_for::<RawLogNum>(), "description": "Tau values by default" },
+                { "type": "string", "description": "Sim all distribution: <sigma> <t1> [t2] ..." }
+            ]
+        })
+    }
+}
+
+/** Resolves the raw user inputs of the data of a query */
+fn resolve_inputs(query_type: &str, data: &mut serde_json::Map<String, Value>) -> Result<(), SimError> {
+    if let Some(Value::String(distribution)) = data.get("values") {
+        let (sigma, values) = parse_sim_all(distribution)?;
+        data.insert("sigma".to_owned(), sigma.into());
+        data.insert("values".to_owned(), serde_json::to_value(values).map_err(|err| SimError::parse(err.to_string()))?);
+        return Ok(());
+    }
+
+    let sigma = data.get("sigma").and_then(Value::as_i64).unwrap_or(0);
+    if let Some(Value::Array(values)) = data.get_mut("values") {
+        for (i, value) in values.iter_mut().enumerate() {
+            if let Value::String(s) = value {
+                let theory = TheoryType::try_from(i)
+                    .map_err(|_| SimError::parse(format!("Invalid value {s} does not match any theory.")))?;
+                *value = serde_json::to_value(parse_currency(s, theory, sigma, 't')?).map_err(|err| SimError::parse(err.to_string()))?;
+            }
+        }
+    }
+
+    if let Some(Value::String(step)) = data.get("step") {
+        let step = parse_exponential_value(step)?;
+        data.insert("step".to_owned(), step.into());
+    }
+
+    let Some(theory) = data.get("theory").cloned() else { return Ok(()) };
+    for field in CURRENCY_FIELDS {
+        if let Some(Value::String(s)) = data.get(field) {
+            let theory: TheoryType = serde_json::from_value(theory.clone())
+                .map_err(|err| SimError::parse(format!("Error parsing query: {err}")))?;
+            let rho = parse_currency(s, theory, sigma, 'r')
+                .map_err(|err| err.context(format_args!("Invalid {field} of the {query_type} query")))?;
+            data.insert(field.to_owned(), serde_json::to_value(rho).map_err(|err| SimError::parse(err.to_string()))?);
+        }
+    }
+    Ok(())
+}
+
+/** Parses a query, resolving the raw user inputs it contains */
+pub fn parse_query(mut query: Value) -> Result<SimQuery, SimError> {
+    if let Value::Object(query) = &mut query {
+        let query_type = query.get("type").and_then(Value::as_str).unwrap_or("").to_owned();
+        match query.get_mut("data") {
+            // Batched queries are resolved separately
+            Some(Value::Object(data)) if query_type != "batch" => resolve_inputs(&query_type, data)?,
+            _ => {}
+        }
+    }
+    serde_json::from_value(query).map_err(|err| SimError::parse(format!("Error parsing query: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn exponential_values() {
+        assert_close(parse_exponential_value("500").unwrap(), 500.);
+        assert_close(parse_exponential_value("e500.5").unwrap(), 500.5);
+        assert_close(parse_exponential_value("1.2e300").unwrap(), 300. + 1.2f64.log10());
+        assert_close(parse_exponential_value("0.5e10").unwrap(), 10.);
+        for s in ["", "e", "1e", "1.e5", "abc", "-5", "1e-5", "1.2.3"] {
+            assert!(parse_exponential_value(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn currencies() {
+        assert_close(parse_currency("e100", TheoryType::BaP, 0, 'r').unwrap().log10_f64(), 100.);
+        assert_close(parse_currency("40t", TheoryType::BaP, 0, 'r').unwrap().log10_f64(), 100.);
+        assert_close(parse_currency(" 150 r", TheoryType::BaP, 0, 't').unwrap().log10_f64(), 150.);
+        assert_close(parse_currency("8.5m", TheoryType::MF, 0, 'r').unwrap().log10_f64(), 50.);
+        assert_close(parse_currency("2m", TheoryType::T1, 100, 'r').unwrap().log10_f64(), (2. - 125f64.log10() + 3f64.log10()) / 0.164);
+        let err = parse_currency("1.5x", TheoryType::BT, 0, 'r').unwrap_err().to_string();
+        assert!(err.starts_with("BT: Invalid currency value 1.5x"), "{err}");
+    }
+
+    #[test]
+    fn sim_all_distributions() {
+        let (sigma, values) = parse_sim_all("70 \n 100 e200 0\n 50r").unwrap();
+        assert_eq!(sigma, 70);
+        assert_eq!(values.len(), 4);
+        assert_close(values[1].log10_f64(), 200.);
+        assert_close(values[3].log10_f64(), 50.);
+
+        assert!(parse_sim_all("70").is_err());
+        assert!(parse_sim_all("-1 100").is_err());
+        assert!(parse_sim_all("70 0 0").is_err());
+        let too_long = format!("70 {}", vec!["1"; TheoryType::TC as usize + 2].join(" "));
+        assert!(parse_sim_all(&too_long).unwrap_err().to_string().contains("does not match any theory"));
+    }
+
+    #[test]
+    fn raw_queries() {
+        let settings = json!({
+            "dt": 1.5, "ddt": 1.0001, "mf_reset_depth": 0, "bought_vars_delta": 5, "sim_all_strats": "all",
+            "completed_cts": "no", "show_a23": false, "show_unofficials": false
+        });
+        let query = parse_query(json!({
+            "type": "step",
+            "data": { "theory": "MF", "strat": "MF", "sigma": 0, "rho": "8.5m", "cap": "1e100", "step": "e10", "custom_strat": null, "settings": settings }
+        })).unwrap();
+        let SimQuery::Step(query) = query else { panic!("Unexpected query {query:?}") };
+        assert_close(query.rho.log10_f64(), 50.);
+        assert_close(query.cap.log10_f64(), 100.);
+        assert_close(query.step.log10_f64(), 10.);
+
+        let query = parse_query(json!({
+            "type": "all",
+            "data": { "values": "20 1 2 3", "very_active": false, "semi_idle": false, "settings": settings }
+        })).unwrap();
+        let SimQuery::All(query) = query else { panic!("Unexpected query {query:?}") };
+        assert_eq!(query.sigma, 20);
+        assert_eq!(query.values.len(), 3);
+
+        let err = parse_query(json!({ "type": "single", "data": { "theory": "MF", "sigma": 0, "rho": "e1x" } })).unwrap_err();
+        assert!(matches!(err, SimError::Parse { .. }));
+        let err = err.to_string();
+        assert!(err.starts_with("Invalid rho of the single query: MF: "), "{err}");
+    }
+}
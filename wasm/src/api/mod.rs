@@ -1,3 +1,6 @@
 pub mod query;
 pub mod response;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod input;
+pub mod error;
+pub mod schema;
\ No newline at end of file
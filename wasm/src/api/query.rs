@@ -2,49 +2,140 @@
  * Contains structures for the different types of queries that can be sent to the wasm module
  */
 
-use serde::Deserialize;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use crate::api::config::TheoryType;
+use crate::api::{config::TheoryType, input::{RawLogNum, RawValues}};
 use crate::utils::{
     lognum::LogNum,
+    schedule::{CheckInSchedule, DailyProfile},
     settings::SimSettings
 };
 
-#[derive(Debug, Deserialize)]
+/** Custom strategy, as buying conditions by variable name */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CustomStratQuery {
+    pub name: Option<String>,
+    pub conditions: IndexMap<String, String>,
+    /** Values of the parameters used in the conditions */
+    #[serde(default)]
+    pub params: IndexMap<String, f64>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SingleSimQuery {
     pub theory: TheoryType, // Maybe change to an enum later
     pub strat: String,
     pub sigma: i64, // Type could change
+    #[schemars(with = "RawLogNum")]
     pub rho: LogNum,
+    #[schemars(with = "Option<RawLogNum>")]
     pub cap: Option<LogNum>,
     pub last_strat: Option<String>,
+    /** Time (seconds) after which the publication happens, if reached before the cap */
+    pub time_cap: Option<f64>,
+    /** Publications only happen when the player checks in */
+    pub check_ins: Option<CheckInSchedule>,
+    /** Buys every available variable, like the in-game autobuy */
+    #[serde(default)]
+    pub autobuy: bool,
+    pub custom_strat: Option<CustomStratQuery>,
+    pub settings: SimSettings
+}
+
+/** Live in-game state of a publication in progress */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ResumeState {
+    pub rho: LogNum,
+    /** Highest rho reached since the publication, defaults to `rho` */
+    pub max_rho: Option<LogNum>,
+    /** Secondary currencies of the theory (q1, r...) */
+    #[serde(default)]
+    pub currencies: IndexMap<String, LogNum>,
+    /** Variable levels, by variable name */
+    #[serde(default)]
+    pub levels: IndexMap<String, i32>,
+    /** Time since the publication (seconds) */
+    pub t: f64,
+    /** Milestone allocation, kept until a new milestone is unlocked */
+    pub milestones: Option<Vec<i32>>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResumeQuery {
+    pub theory: TheoryType,
+    pub strat: String,
+    pub sigma: i64,
+    /** rho of the last publication */
+    #[schemars(with = "RawLogNum")]
+    pub last_pub: LogNum,
+    pub last_strat: Option<String>,
+    pub state: ResumeState,
     pub settings: SimSettings
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ChainSimQuery {
     pub theory: TheoryType, // Maybe change to an enum later
     pub strat: String,
     pub sigma: i64, // Type could change
+    #[schemars(with = "RawLogNum")]
     pub rho: LogNum,
-    pub cap: LogNum,
+    #[schemars(with = "Option<RawLogNum>")]
+    pub cap: Option<LogNum>,
     pub hard_cap: bool,
+    /** The chain stops once tau is multiplied by this target */
+    pub tau_target: Option<LogNum>,
+    /** The chain stops after this time (seconds), the last publication may be partial */
+    pub time_budget: Option<f64>,
+    /** Publications only happen when the player checks in */
+    pub check_ins: Option<CheckInSchedule>,
+    /**
+     * Strategy category of each time of the day, overriding `strat`
+     *
+     * Publications started while the player is offline use the idle category with autobuy.
+     */
+    pub daily_profile: Option<DailyProfile>,
+    pub custom_strat: Option<CustomStratQuery>,
     pub settings: SimSettings
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct StepSimQuery {
     pub theory: TheoryType, // Maybe change to an enum later
     pub strat: String,
     pub sigma: i64, // Type could change
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub cap: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub step: LogNum,
+    pub custom_strat: Option<CustomStratQuery>,
+    pub settings: SimSettings
+}
+
+/** Chain sims started at every step of a rho range, like a step sim */
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StepChainQuery {
+    pub theory: TheoryType,
+    pub strat: String,
+    pub sigma: i64,
+    #[schemars(with = "RawLogNum")]
     pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
     pub cap: LogNum,
+    #[schemars(with = "RawLogNum")]
     pub step: LogNum,
+    pub hard_cap: bool,
+    pub custom_strat: Option<CustomStratQuery>,
     pub settings: SimSettings
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SimAllQuery {
+    #[schemars(with = "RawValues")]
     pub values: Vec<LogNum>,
     pub sigma: i64, // Type could change
     pub very_active: bool,
@@ -52,11 +143,211 @@ pub struct SimAllQuery {
     pub settings: SimSettings
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PlanQuery {
+    /** Last publication of each theory, like `SimAllQuery` */
+    #[schemars(with = "RawValues")]
+    pub values: Vec<LogNum>,
+    pub sigma: i64,
+    /** Time (seconds) covered by the plan */
+    pub horizon: f64,
+    /** Strategy category used when there is no daily profile */
+    pub strat: String,
+    /** Strategy category of each time of the day, overriding `strat` */
+    pub daily_profile: Option<DailyProfile>,
+    pub settings: SimSettings
+}
+
+/** Tick settings of a sim */
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct TickSettings {
+    pub dt: f64,
+    pub ddt: f64
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvergenceQuery {
+    pub theory: TheoryType,
+    pub strat: String,
+    pub sigma: i64,
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    pub last_strat: Option<String>,
+    /** Tick settings compared to the exact game tick, a default ladder is used if None */
+    pub ladder: Option<Vec<TickSettings>>,
+    /** Relative tau/h error accepted for the recommended settings */
+    pub tolerance: Option<f64>,
+    pub settings: SimSettings
+}
+
+/** Range searched for a parameter of a tuned strategy */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ParamRange {
+    pub min: f64,
+    pub max: f64,
+    /**
+     * Value the tuned parameters are compared against, and from which the search starts
+     *
+     * Defaults to the value of the built-in weight or of the custom strategy parameter, or to the
+     * middle of the range.
+     */
+    pub default: Option<f64>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TuneQuery {
+    pub theory: TheoryType,
+    /** Strategy on which the custom strategy is applied */
+    pub strat: String,
+    pub sigma: i64,
+    /** Strategy whose parameters are tuned, the built-in weights of `strat` are tuned if None */
+    pub custom_strat: Option<CustomStratQuery>,
+    pub params: IndexMap<String, ParamRange>,
+    /** rho range in which the strategy is tuned, sampled like a step sim */
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub cap: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub step: LogNum,
+    /** Maximum number of parameter sets evaluated */
+    pub max_evals: Option<u32>,
+    pub settings: SimSettings
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CrossoverQuery {
+    pub theory: TheoryType,
+    /** The two strategies (or strategy categories) compared */
+    pub strats: [String; 2],
+    pub sigma: i64,
+    /** rho interval searched, sampled like a step sim (at most 1000 rho values) before refining */
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub cap: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub step: LogNum,
+    /** Width (log10 of rho) under which a crossover is considered located */
+    pub precision: Option<f64>,
+    pub settings: SimSettings
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompareQuery {
+    pub theory: TheoryType,
+    /** Strategies (or strategy categories) compared, the first one is the reference */
+    pub strats: Vec<String>,
+    pub sigma: i64,
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub cap: LogNum,
+    /** Step between the compared publications, the strategies are chained from `rho` to `cap` without being compared if None */
+    #[schemars(with = "Option<RawLogNum>")]
+    pub step: Option<LogNum>,
+    pub settings: SimSettings
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FilterSweepQuery {
+    pub theory: TheoryType,
+    pub sigma: i64,
+    /** rho range swept, sampled like a step sim; grid points times candidates is at most 2000 */
+    #[schemars(with = "RawLogNum")]
+    pub rho: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub cap: LogNum,
+    #[schemars(with = "RawLogNum")]
+    pub step: LogNum,
+    /** Relative tau/h loss under which a strategy is considered as good as the best one */
+    pub tolerance: Option<f64>,
+    /**
+     * Strategies considered in each strategy category
+     *
+     * Categories missing from the map, or all of them if None, use the strategies whose current
+     * filter condition holds somewhere in the range.
+     */
+    pub candidates: Option<IndexMap<String, Vec<String>>>,
+    pub settings: SimSettings
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PubTableQuery {
+    /** Only BaP is supported, the sims of CSR2, EF and FP are not ported */
+    pub theory: TheoryType,
+    /** Strategy used to reach the next publications */
+    pub strat: String,
+    pub sigma: i64,
+    /**
+     * Range of last publications (log10) regenerated, the whole table by default
+     *
+     * At most 200 entries can be regenerated by one query, so the table is regenerated in ranges.
+     */
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+    pub settings: SimSettings
+}
+
+/** Unit of a value converted by a convert query */
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueUnit {
+    Rho,
+    Tau,
+    /** Total multiplier of the theory */
+    Multiplier
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvertQuery {
+    pub theory: TheoryType,
+    pub sigma: i64,
+    pub value: LogNum,
+    pub unit: ValueUnit
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DescribeQuery {
+    /** Theory described, every theory if None */
+    pub theory: Option<TheoryType>,
+    /** Last publication at which the milestone maxima are computed, the last milestone unlock by default */
+    #[schemars(with = "Option<RawLogNum>")]
+    pub rho: Option<LogNum>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RzZerosQuery {
+    /** Start of the range of t searched, at least 1 */
+    pub from: f64,
+    /** End of the range of t searched */
+    pub to: f64,
+    /** Grid points per average zero spacing used to bracket zeros, 8 if None and at most 64 */
+    pub resolution: Option<u32>,
+    /** Minimum ratio of the derivative of a zero to the best derivative before it, 1 if None */
+    pub min_ratio: Option<f64>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum SimQuery {
     Single(SingleSimQuery),
+    Resume(ResumeQuery),
     Chain(ChainSimQuery),
     Step(StepSimQuery),
-    All(SimAllQuery)
+    /** Replies with a step sim response, one result per starting rho */
+    StepChain(StepChainQuery),
+    All(SimAllQuery),
+    Plan(PlanQuery),
+    Convergence(ConvergenceQuery),
+    Zeros(RzZerosQuery),
+    Tune(TuneQuery),
+    Crossover(CrossoverQuery),
+    Compare(CompareQuery),
+    FilterSweep(FilterSweepQuery),
+    PubTable(PubTableQuery),
+    Describe(DescribeQuery),
+    Convert(ConvertQuery),
+    /** Queries run together, sharing identical sims; each query is parsed and run separately */
+    Batch(Vec<serde_json::Value>)
 }
\ No newline at end of file
@@ -2,34 +2,55 @@
  * Contains structures for the different types of responses that can be replied by the wasm module
  */
 
-
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::Serialize;
 
+use crate::api::{config::TheoryType, error::SimError, query::TickSettings};
+use crate::theories::cts::rz_zeros::{GoodZeros, RankedZero};
 use crate::utils::{
+    cost::CostModel,
     lognum::LogNum,
     result::{SimResult, SimAllResult},
-    settings
+    settings,
+    value::ValueModel,
+    var_buy::VarBuy
 };
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SingleSimResponse {
     pub result: SimResult
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResumeResponse {
+    /** Best publication, its purchase list only contains the remaining purchases */
+    pub result: SimResult,
+    /** Time left until the publication (seconds), 0 if the publication should happen now */
+    pub remaining_time: f64
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ChainSimResponse {
     pub results: Vec<SimResult>,
     pub delta_tau: LogNum,
     pub average_rate: f64,
-    pub total_time: f64
+    pub total_time: f64,
+    /**
+     * Time needed to reach the tau target, None without tau target
+     *
+     * If the chain stopped before the target (time budget or cap), the time of the missing tau is
+     * extrapolated from `average_rate`.
+     */
+    pub eta: Option<f64>
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct StepSimResponse {
     pub results: Vec<SimResult>
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SimAllResponse {
     pub sigma: i64,
     pub strat_type: settings::SimAllStrats,
@@ -37,11 +58,279 @@ pub struct SimAllResponse {
     pub results: Vec<SimAllResult>
 }
 
-#[derive(Debug, Serialize)]
-#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+/** Consecutive publications of the same theory in a rotation plan */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PlanStep {
+    pub theory: TheoryType,
+    /** Strategy category of the publications */
+    pub strat: String,
+    pub pubs: u32,
+    pub results: Vec<SimResult>,
+    /** Time (seconds) since the start of the plan at which the step starts */
+    pub start_time: f64,
+    pub time: f64,
+    /** Tau gain of the step (log10) */
+    pub tau_gain: f64,
+    /** Sum of the tau gains (log10) of the plan at the end of the step */
+    pub cumulative_tau: f64,
+    pub cumulative_time: f64
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PlanResponse {
+    pub steps: Vec<PlanStep>,
+    /** Updated last publication of each theory, in the order of the query values */
+    pub values: Vec<LogNum>,
+    pub total_tau: f64,
+    pub total_time: f64
+}
+
+/** Result of a sim with given tick settings, compared to the exact game tick */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConvergenceEntry {
+    pub tick_settings: TickSettings,
+    pub result: SimResult,
+    /** Number of ticks simulated, used as the cost of the settings */
+    pub ticks: u64,
+    /** Relative tau/h error */
+    pub tau_h_error: f64,
+    /** Relative publication time error */
+    pub time_error: f64,
+    /** Publication rho error (log10) */
+    pub rho_error: f64
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConvergenceResponse {
+    /** Result with the exact game tick (dt = 0.15, ddt = 1) */
+    pub reference: SimResult,
+    /** Entries in the order of the ladder */
+    pub entries: Vec<ConvergenceEntry>,
+    /** Cheapest settings within the tolerance, the exact game tick if no settings of the ladder are accurate enough */
+    pub recommended: TickSettings
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TuneResponse {
+    /** Best parameters found */
+    pub params: IndexMap<String, f64>,
+    /** Average tau/h with the best parameters */
+    pub tau_h: f64,
+    /** Average tau/h with the default parameters */
+    pub default_tau_h: f64,
+    /** Relative tau/h gain of the best parameters over the default ones, None if the default tau/h is 0 */
+    pub gain: Option<f64>,
+    /** Number of parameter sets evaluated */
+    pub evaluations: u32,
+    /** Results with the best parameters */
+    pub results: Vec<SimResult>
+}
+
+/** tau/h of both strategies at a given rho */
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CrossoverSide {
+    pub rho: LogNum,
+    pub tau_h: [f64; 2]
+}
+
+/** Point where the best of the two strategies changes */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrossoverPoint {
+    pub rho: LogNum,
+    pub below: CrossoverSide,
+    pub above: CrossoverSide
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CrossoverResponse {
+    pub strats: [String; 2],
+    pub crossovers: Vec<CrossoverPoint>
+}
+
+/** First purchase differing between a strategy and the reference strategy */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PurchaseDivergence {
+    /** Index of the purchase in both purchase lists */
+    pub index: usize,
+    /** Purchase of the reference strategy, None if its list ended */
+    pub reference: Option<VarBuy>,
+    /** Purchase of the compared strategy, None if its list ended */
+    pub other: Option<VarBuy>
+}
+
+/** Result of a strategy compared to the reference strategy */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompareEntry {
+    pub result: SimResult,
+    /** tau/h of this strategy divided by the tau/h of the reference, None for unaligned rows */
+    pub tau_h_ratio: Option<f64>,
+    /** Publication time of this strategy minus the one of the reference, None for unaligned rows */
+    pub time_delta: Option<f64>,
+    /** None if both purchase lists are identical, or for unaligned rows */
+    pub divergence: Option<PurchaseDivergence>
+}
+
+/**
+ * Results of the strategies for the same step, or the n-th publication of each chain
+ *
+ * Rows are aligned when all their entries start at the same rho: every row of a step comparison,
+ * and the first row of chains. Entries are None once a chain has ended.
+ */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompareRow {
+    /**
+     * rho at which every entry starts, None for unaligned rows
+     *
+     * The entries of unaligned rows start at the `last_pub` of their result.
+     */
+    pub rho: Option<LogNum>,
+    pub entries: Vec<Option<CompareEntry>>
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompareResponse {
+    pub strats: Vec<String>,
+    pub rows: Vec<CompareRow>,
+    /** Total time of each strategy */
+    pub total_time: Vec<f64>,
+    /** Chain mode only: average tau gain rate of each chain divided by the one of the first strategy */
+    pub rate_ratio: Option<Vec<f64>>,
+    /** Chain mode only: total time of each chain minus the one of the first strategy */
+    pub time_delta: Option<Vec<f64>>
+}
+
+/** Filter condition of a strategy proposed by a sweep */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StratFilterDiff {
+    pub strat: String,
+    pub current: String,
+    pub proposed: String,
+    /** True if both conditions disagree somewhere in the swept range */
+    pub changed: bool
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FilterSweepResponse {
+    pub theory: TheoryType,
+    pub conditions: Vec<StratFilterDiff>
+}
+
+/** Entry of a publication table whose next publication changed */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PubTableChange {
+    /** Last publication (log10) */
+    pub key: f64,
+    pub old_next: Option<f64>,
+    pub new_next: f64,
+    pub old_time: Option<f64>,
+    pub new_time: f64
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PubTableResponse {
+    pub theory: TheoryType,
+    /** Regenerated table, in the schema of the committed one */
+    pub table: serde_json::Value,
+    pub changes: Vec<PubTableChange>
+}
+
+/** Strategy of a theory as configured in data.json */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StratDescription {
+    pub name: String,
+    pub filter_condition: String,
+    pub forced_condition: Option<String>,
+    pub ui_visible: bool
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VariableDescription {
+    pub name: String,
+    pub cost: CostModel,
+    pub value: ValueModel
+}
+
+/** Publication, milestones and variables of a theory sim */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TheoryModel {
+    /** rho (log10) at which publications are unlocked */
+    pub pub_unlock: f64,
+    /** Milestone unlock points (log10 of rho), unused if `milestone_unlock_steps` is set */
+    pub milestone_unlocks: Vec<f64>,
+    /** Steps of rho (log10) at which milestones are unlocked */
+    pub milestone_unlock_steps: Option<f64>,
+    pub milestones_max: Vec<i32>,
+    pub variables: Vec<VariableDescription>
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TheoryDescription {
+    pub theory: TheoryType,
+    pub tau_factor: f64,
+    pub ui_visible: bool,
+    pub unofficial: bool,
+    pub strats: Vec<StratDescription>,
+    /** None if the theory is not supported by the sim yet */
+    pub model: Option<TheoryModel>
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DescribeResponse {
+    /** Version tag of the config in use, None for the embedded data.json */
+    pub config_version: Option<String>,
+    pub strat_categories: Vec<String>,
+    pub theories: Vec<TheoryDescription>
+}
+
+/** Last publication of a theory, as rho, tau and total multiplier */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConvertResponse {
+    pub theory: TheoryType,
+    pub rho: LogNum,
+    pub tau: LogNum,
+    pub multiplier: LogNum
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RzZerosResponse {
+    pub zeros: Vec<RankedZero>,
+    pub table: GoodZeros
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum SimResponse {
     Single(SingleSimResponse),
+    Resume(ResumeResponse),
     Chain(ChainSimResponse),
     Step(StepSimResponse),
-    All(SimAllResponse)
+    All(SimAllResponse),
+    Plan(PlanResponse),
+    Convergence(ConvergenceResponse),
+    Zeros(RzZerosResponse),
+    Tune(TuneResponse),
+    Crossover(CrossoverResponse),
+    Compare(CompareResponse),
+    FilterSweep(FilterSweepResponse),
+    PubTable(PubTableResponse),
+    Describe(DescribeResponse),
+    Convert(ConvertResponse),
+    /** Responses of the batched queries, in the order of the queries */
+    Batch(Vec<ApiResponse>)
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "response_type", content = "data", rename_all = "lowercase")]
+pub enum ApiResponse {
+    Success(SimResponse),
+    Failure(SimError)
+}
+
+impl From<Result<SimResponse, SimError>> for ApiResponse {
+    fn from(res: Result<SimResponse, SimError>) -> Self {
+        match res {
+            Ok(res) => ApiResponse::Success(res),
+            Err(err) => ApiResponse::Failure(err)
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,281 @@
+/*!
+ * Versioned schema of the API of the wasm module
+ *
+ * The JSON Schema of the queries and responses is generated from the Rust types, and the
+ * TypeScript declarations of the UI are generated from that schema.
+ */
+
+use schemars::{JsonSchema, generate::SchemaSettings};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::api::{query::SimQuery, response::ApiResponse};
+
+/** Version of the API, increased on every breaking change of the queries or responses */
+pub const API_VERSION: u32 = 1;
+
+/** Query sent to the wasm module */
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+pub struct ApiQuery {
+    /** Version of the API the query is written for, the current version if missing */
+    pub api_version: Option<u32>,
+    /** Overrides of data.json for this query only, as a JSON merge patch */
+    pub config: Option<Value>,
+    #[serde(flatten)]
+    pub query: SimQuery
+}
+
+/** Response of the wasm module */
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiReply {
+    /** Version of the API of the module */
+    pub api_version: u32,
+    #[serde(flatten)]
+    pub response: ApiResponse
+}
+
+impl From<ApiResponse> for ApiReply {
+    fn from(response: ApiResponse) -> Self {
+        ApiReply { api_version: API_VERSION, response }
+    }
+}
+
+/** Returns the JSON Schema of the queries (ApiQuery) and responses (ApiReply) of the API */
+pub fn api_schema() -> Value {
+    // Queries are only deserialized and responses only serialized, which changes the required fields
+    let mut queries = SchemaSettings::draft2020_12().for_deserialize().into_generator();
+    queries.subschema_for::<ApiQuery>();
+    let mut responses = SchemaSettings::draft2020_12().for_serialize().into_generator();
+    responses.subschema_for::<ApiReply>();
+
+    let mut defs = queries.take_definitions(true);
+    for (name, schema) in responses.take_definitions(true) {
+        match defs.get(&name) {
+            // Types used in both directions keep the schema of the queries
+            Some(query_schema) if *query_schema != schema => {
+                defs.insert(format!("{name}Response"), schema);
+            },
+            _ => {
+                defs.insert(name, schema);
+            }
+        }
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "sim-4-rust API",
+        "api_version": API_VERSION,
+        "$defs": defs
+    })
+}
+
+/** Returns true if `separator` appears in `ts` outside of brackets and strings */
+fn has_top_level(ts: &str, separator: &str) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    for (i, c) in ts.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' | '[' | '(' if !in_string => depth += 1,
+            '}' | ']' | ')' if !in_string => depth -= 1,
+            _ if depth == 0 && !in_string && ts[i..].starts_with(separator) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/** Wraps a type in parentheses if it is a union or an intersection, unions joined with `|` are kept as is */
+fn group(ts: String, separator: &str) -> String {
+    let needs_group = has_top_level(&ts, " & ") || (separator != " | " && has_top_level(&ts, " | "));
+    if needs_group { format!("({ts})") } else { ts }
+}
+
+/** Writes a JSDoc comment from the description of a schema */
+fn doc_comment(schema: &Value, indent: &str) -> String {
+    match schema.get("description").and_then(Value::as_str) {
+        Some(description) => {
+            let lines: Vec<&str> = description.lines().collect();
+            if lines.len() == 1 {
+                format!("{indent}/** {} */\n", lines[0])
+            } else {
+                let body: String = lines.iter().map(|line| format!("{indent} * {line}\n").replace(" \n", "\n")).collect();
+                format!("{indent}/**\n{body}{indent} */\n")
+            }
+        },
+        None => String::new()
+    }
+}
+
+/** Writes the TypeScript type of an object schema, None if the schema has no properties */
+fn ts_object(schema: &Map<String, Value>, indent: &str) -> Option<String> {
+    let required: Vec<&str> = schema.get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let inner = format!("{indent}  ");
+    let properties = schema.get("properties").and_then(Value::as_object).filter(|properties| !properties.is_empty());
+    // Maps are written inline
+    match (properties, schema.get("additionalProperties")) {
+        (None, Some(Value::Bool(true))) => return Some("{ [key: string]: unknown }".to_owned()),
+        (None, Some(additional @ Value::Object(_))) => return Some(format!("{{ [key: string]: {} }}", ts_type(additional, indent))),
+        _ => {}
+    }
+    let mut fields = String::new();
+    if let Some(properties) = properties {
+        for (name, property) in properties {
+            let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+            fields.push_str(&doc_comment(property, &inner));
+            fields.push_str(&format!("{inner}{name}{optional}: {};\n", ts_type(property, &inner)));
+        }
+    }
+    match schema.get("additionalProperties") {
+        Some(Value::Bool(false)) | None => {},
+        Some(Value::Bool(true)) => fields.push_str(&format!("{inner}[key: string]: unknown;\n")),
+        Some(additional) => fields.push_str(&format!("{inner}[key: string]: {};\n", ts_type(additional, &inner)))
+    }
+    (!fields.is_empty()).then(|| format!("{{\n{fields}{indent}}}"))
+}
+
+/** Writes the TypeScript type of a schema */
+fn ts_type(schema: &Value, indent: &str) -> String {
+    let schema = match schema {
+        Value::Bool(true) => return "unknown".to_owned(),
+        Value::Bool(false) => return "never".to_owned(),
+        Value::Object(schema) => schema,
+        _ => return "unknown".to_owned()
+    };
+    let union = |schemas: &Value, separator: &str| {
+        schemas.as_array().into_iter().flatten()
+            .map(|schema| group(ts_type(schema, indent), separator))
+            .collect::<Vec<_>>()
+            .join(separator)
+    };
+
+    let mut parts = Vec::new();
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        parts.push(reference.rsplit('/').next().unwrap_or(reference).to_owned());
+    }
+    if let Some(value) = schema.get("const") {
+        parts.push(value.to_string());
+    } else if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        parts.push(values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | "));
+    } else if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            types => types.as_str().into_iter().collect()
+        };
+        let ts_types: Vec<String> = types.into_iter()
+            .map(|ty| match ty {
+                "string" => "string".to_owned(),
+                "number" | "integer" => "number".to_owned(),
+                "boolean" => "boolean".to_owned(),
+                "null" => "null".to_owned(),
+                "array" => match (schema.get("prefixItems"), schema.get("items")) {
+                    (Some(Value::Array(items)), _) => format!("[{}]", items.iter().map(|item| ts_type(item, indent)).collect::<Vec<_>>().join(", ")),
+                    (_, Some(items)) => format!("{}[]", group(ts_type(items, indent), "[]")),
+                    _ => "unknown[]".to_owned()
+                },
+                "object" => ts_object(schema, indent).unwrap_or("{ [key: string]: unknown }".to_owned()),
+                _ => "unknown".to_owned()
+            })
+            .collect();
+        parts.push(ts_types.join(" | "));
+    } else if schema.contains_key("properties") {
+        parts.extend(ts_object(schema, indent));
+    }
+    for (key, separator) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(schemas) = schema.get(key) {
+            parts.push(union(schemas, separator));
+        }
+    }
+
+    match parts.len() {
+        0 => "unknown".to_owned(),
+        1 => parts.remove(0),
+        _ => parts.into_iter().map(|part| group(part, " & ")).collect::<Vec<_>>().join(" & ")
+    }
+}
+
+/** Returns the TypeScript declarations of the types of the JSON Schema `schema` */
+pub fn typescript(schema: &Value) -> String {
+    let mut ts = String::from("/*\n * Types of the API of the wasm module, generated from its JSON Schema. Do not edit.\n */\n\n");
+    ts.push_str(&format!("export const API_VERSION = {API_VERSION};\n"));
+    if let Some(Value::Object(defs)) = schema.get("$defs") {
+        for (name, def) in defs {
+            ts.push('\n');
+            ts.push_str(&doc_comment(def, ""));
+            ts.push_str(&format!("export type {name} = {};\n", ts_type(def, "")));
+        }
+    }
+    ts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/api.schema.json");
+    const TS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../src/Utils/api.types.ts");
+
+    /** Regenerates the schema files with `UPDATE_API_SCHEMA=1 cargo test schema_files` */
+    #[test]
+    fn schema_files() {
+        let schema = api_schema();
+        let json = serde_json::to_string_pretty(&schema).unwrap() + "\n";
+        let ts = typescript(&schema);
+        if std::env::var_os("UPDATE_API_SCHEMA").is_some() {
+            std::fs::create_dir_all(std::path::Path::new(SCHEMA_PATH).parent().unwrap()).unwrap();
+            std::fs::write(SCHEMA_PATH, json).unwrap();
+            std::fs::write(TS_PATH, ts).unwrap();
+            return;
+        }
+        let outdated = "the API schema is outdated, regenerate it with UPDATE_API_SCHEMA=1 cargo test schema_files";
+        assert_eq!(std::fs::read_to_string(SCHEMA_PATH).expect(outdated), json, "{outdated}");
+        assert_eq!(std::fs::read_to_string(TS_PATH).expect(outdated), ts, "{outdated}");
+    }
+
+    #[test]
+    fn covers_every_variant() {
+        let schema = api_schema();
+        let ts = typescript(&schema);
+        for variant in ["single", "resume", "chain", "step", "all", "plan", "convergence", "zeros", "tune", "crossover", "compare", "filter_sweep", "pub_table", "describe", "convert", "batch"] {
+            assert!(ts.matches(&format!("type: \"{variant}\";")).count() >= 2, "{variant}");
+        }
+        // Step chain sims reply with step sim responses
+        assert_eq!(ts.matches("type: \"step_chain\";").count(), 1);
+        for code in ["parse", "config", "unknown_strategy", "invalid_query", "numeric", "limit_exceeded", "unsupported_version", "internal"] {
+            assert!(ts.contains(&format!("code: \"{code}\";")), "{code}");
+        }
+        assert!(ts.contains("export type ApiQuery = ") && ts.contains("export type ApiReply = "));
+    }
+
+    #[test]
+    fn raw_inputs() {
+        let ts = typescript(&api_schema());
+        assert!(ts.contains("export type RawLogNum = LogNum | string;"));
+        assert!(ts.contains("export type RawValues = RawLogNum[] | string;"));
+        for field in ["rho: RawLogNum;", "cap?: RawLogNum | null;", "last_pub: RawLogNum;", "step: RawLogNum;", "values: RawValues;"] {
+            assert!(ts.contains(field), "{field}");
+        }
+        // The state of a resumed publication is not resolved
+        assert!(ts.contains("max_rho?: LogNum | null;"));
+    }
+
+    #[test]
+    fn typescript_types() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": ["number", "null"] },
+                "b": { "type": "array", "items": { "$ref": "#/$defs/B" } },
+                "c": { "type": "array", "prefixItems": [{ "type": "string" }, { "type": "boolean" }] },
+                "d": { "oneOf": [{ "const": "x" }, { "enum": ["y", "z"] }] },
+                "e": { "type": "object", "additionalProperties": { "type": "integer" } }
+            },
+            "required": ["b"]
+        });
+        assert_eq!(ts_type(&schema, ""), "{\n  a?: number | null;\n  b: B[];\n  c?: [string, boolean];\n  d?: \"x\" | \"y\" | \"z\";\n  e?: { [key: string]: number };\n}");
+    }
+}
@@ -1,64 +1,72 @@
-#[allow(dead_code)]
+#[allow(dead_code, clippy::empty_line_after_doc_comments)]
 mod utils;
-#[allow(dead_code)]
+#[allow(dead_code, clippy::empty_line_after_doc_comments)]
 mod api;
 #[allow(dead_code, unused_variables)]
 mod sim;
+#[allow(dead_code)]
+mod theories;
 
-use std::sync::OnceLock;
-use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 use api::{
-    query::SimQuery,
-    response::SimResponse,
-    config::Config
+    config,
+    error::{SimError, catch_panic},
+    response::ApiResponse,
+    schema::{API_VERSION, ApiReply, api_schema}
 };
-use sim::simulate;
+use sim::run;
 
-/** Holds the configuration on the sim (data.json) */
-static CONFIG: OnceLock<Config> = OnceLock::new();
-const DEFAULT_ERR: &str = "{\"response_type\": \"failure\", \"data\":\"API Error\"}";
+/** Reply of the module when a response cannot be serialized */
+fn default_err() -> String {
+    format!("{{\"api_version\": {API_VERSION}, \"response_type\": \"failure\", \"data\": {{\"code\": \"internal\", \"message\": \"API Error\"}}}}")
+}
 
-#[derive(Serialize)]
-#[serde(tag = "response_type", content = "data", rename_all = "lowercase")]
-enum ApiResponse {
-    Success(SimResponse),
-    Failure(String)
+/** Serializes a response of the module */
+fn to_json<T: serde::Serialize>(res: &Result<T, SimError>) -> String {
+    let res = match res {
+        Ok(res) => serde_json::to_string(res),
+        Err(err) => serde_json::to_string(&ApiReply::from(ApiResponse::Failure(err.clone())))
+    };
+    res.unwrap_or_else(|_| default_err())
 }
 
 /**
- * Sets the config (data.json) for the wasm module
+ * Sets the config (data.json) for the wasm module, replacing the current one
+ *
+ * Setting a config is optional, the data.json embedded in the module is used until then.
+ * Returns the versions of the new config and of the replaced one.
  */
 #[wasm_bindgen] 
-pub fn set_config(config: &str) -> String { 
-    let cfg: Config = serde_json::from_str(config).unwrap(); 
-    CONFIG.set(cfg).expect("Config already set"); 
-
-    format!("{:?}", CONFIG.get())
-}
-
-fn create_error(msg: &str) -> String {
-    serde_json::to_string(&ApiResponse::Failure(msg.to_owned()))
-        .unwrap_or(DEFAULT_ERR.to_owned())
+pub fn set_config(config: &str, version: Option<String>) -> String { 
+    let res = catch_panic(|| {
+        let previous = config::set_config(config, version.clone())?;
+        Ok(serde_json::json!({
+            "api_version": API_VERSION,
+            "response_type": "success",
+            "data": { "version": version, "previous_version": previous.version }
+        }))
+    });
+    to_json(&res)
 }
 
 /**
  * Main API function of the wasm module
  */
 #[wasm_bindgen]
-#[allow(unused_variables)]
 pub fn main(input: &str) -> String {
-    let query = match serde_json::from_str::<SimQuery>(input) {
-        Ok(query) => query,
-        Err(err) => return create_error(&format!("Error parsing query: {err}"))
-    };
-
-    let res = match simulate(query) {
-        Ok(res) => res,
-        Err(err) => return create_error(&err) 
-    };
+    let res = catch_panic(|| {
+        let query = serde_json::from_str(input)
+            .map_err(|err| SimError::parse(format!("Error parsing query: {err}")))?;
+        run(query).map(|res| ApiReply::from(ApiResponse::Success(res)))
+    });
+    to_json(&res)
+}
 
-    serde_json::to_string(&ApiResponse::Success(res))
-        .unwrap_or(DEFAULT_ERR.to_owned())
+/**
+ * Returns the JSON Schema of the queries and responses of the wasm module
+ */
+#[wasm_bindgen]
+pub fn schema() -> String {
+    api_schema().to_string()
 }
\ No newline at end of file
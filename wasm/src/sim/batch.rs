@@ -0,0 +1,300 @@
+/*!
+ * Batched queries
+ *
+ * The queries of a batch are run one after the other and fail independently. While a batch runs,
+ * the result of every theory sim is cached, so identical sims of different queries are only
+ * computed once.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::api::{
+    config::{TheoryType, current_config},
+    error::{SimError, catch_panic},
+    query::ResumeState,
+    response::ApiResponse
+};
+use crate::theories::{custom_strat::CustomStrat, simulate_theory, theory::TheoryData};
+use crate::utils::{lognum::LogNum, result::SimResult, schedule::CheckInSchedule, settings::SimSettings};
+
+use super::run;
+
+/** Bits of a LogNum, floats are compared bitwise so that only identical inputs share a sim */
+type LogNumBits = (u64, i8);
+
+fn lognum_bits(x: LogNum) -> LogNumBits {
+    (x.value.to_bits(), x.sign)
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CheckInKey {
+    times: Vec<u64>,
+    period: u64,
+    offset: u64,
+    buy_at_check_ins: bool
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct ResumeKey {
+    rho: LogNumBits,
+    max_rho: Option<LogNumBits>,
+    currencies: Vec<(String, LogNumBits)>,
+    levels: Vec<(String, i32)>,
+    t: u64,
+    milestones: Option<Vec<i32>>
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CustomStratKey {
+    name: Option<String>,
+    conditions: Vec<(String, String)>,
+    params: Vec<(String, u64)>
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct SettingsKey {
+    dt: u64,
+    ddt: u64,
+    mf_reset_depth: i32,
+    bought_vars_delta: i32,
+    sim_all_strats: u8,
+    completed_cts: u8,
+    show_a23: bool,
+    show_unofficials: bool
+}
+
+/** Fields of a sim that determine its result, with the id of its config */
+#[derive(PartialEq, Eq, Hash)]
+struct SimKey {
+    config_id: u64,
+    theory: TheoryType,
+    sigma: i64,
+    rho: LogNumBits,
+    strat: String,
+    cap: Option<LogNumBits>,
+    time_cap: Option<u64>,
+    check_ins: Option<CheckInKey>,
+    autobuy: bool,
+    resume: Option<ResumeKey>,
+    custom_strat: Option<CustomStratKey>,
+    settings: SettingsKey
+}
+
+impl SimKey {
+    fn new(config_id: u64, data: &TheoryData) -> Self {
+        // Fields are destructured without `..` so that a new field cannot be left out of the key
+        let check_in_key = |schedule: &CheckInSchedule| {
+            let CheckInSchedule { times, period, offset, buy_at_check_ins } = schedule;
+            CheckInKey {
+                times: times.iter().map(|time| time.to_bits()).collect(),
+                period: period.to_bits(),
+                offset: offset.to_bits(),
+                buy_at_check_ins: *buy_at_check_ins
+            }
+        };
+        let resume_key = |state: &ResumeState| {
+            let ResumeState { rho, max_rho, currencies, levels, t, milestones } = state;
+            ResumeKey {
+                rho: lognum_bits(*rho),
+                max_rho: max_rho.map(lognum_bits),
+                currencies: currencies.iter().map(|(name, value)| (name.clone(), lognum_bits(*value))).collect(),
+                levels: levels.iter().map(|(name, level)| (name.clone(), *level)).collect(),
+                t: t.to_bits(),
+                milestones: milestones.clone()
+            }
+        };
+        let custom_strat_key = |custom: &Arc<CustomStrat>| CustomStratKey {
+            name: custom.query.name.clone(),
+            conditions: custom.query.conditions.iter().map(|(var, condition)| (var.clone(), condition.clone())).collect(),
+            params: custom.query.params.iter().map(|(name, value)| (name.clone(), value.to_bits())).collect()
+        };
+        let TheoryData { theory, sigma, rho, strat, cap, time_cap, check_ins, autobuy, resume, custom_strat, settings } = data;
+        let SimSettings { dt, ddt, mf_reset_depth, bought_vars_delta, sim_all_strats, completed_cts, show_a23, show_unofficials } = settings;
+        SimKey {
+            config_id,
+            theory: *theory,
+            sigma: *sigma,
+            rho: lognum_bits(*rho),
+            strat: strat.clone(),
+            cap: cap.map(lognum_bits),
+            time_cap: time_cap.map(f64::to_bits),
+            check_ins: check_ins.as_ref().map(check_in_key),
+            autobuy: *autobuy,
+            resume: resume.as_ref().map(resume_key),
+            custom_strat: custom_strat.as_ref().map(custom_strat_key),
+            settings: SettingsKey {
+                dt: dt.to_bits(),
+                ddt: ddt.to_bits(),
+                mf_reset_depth: *mf_reset_depth,
+                bought_vars_delta: *bought_vars_delta,
+                sim_all_strats: sim_all_strats.clone() as u8,
+                completed_cts: completed_cts.clone() as u8,
+                show_a23: *show_a23,
+                show_unofficials: *show_unofficials
+            }
+        }
+    }
+}
+
+/** Results of the sims of the running batch, keyed by their config and data */
+#[derive(Default)]
+struct SimCache {
+    results: HashMap<SimKey, SimResult>,
+    /** Number of sims taken from the cache */
+    hits: u32
+}
+
+thread_local! {
+    /** Cache of the running batch, None outside of batches */
+    static SIM_CACHE: RefCell<Option<SimCache>> = const { RefCell::new(None) };
+}
+
+/** Runs the sim of `data`, reusing the result of an identical sim of the running batch if there is one */
+pub fn simulate_cached(data: TheoryData) -> Result<SimResult, SimError> {
+    let key = SimKey::new(current_config()?.id, &data);
+    let cached = SIM_CACHE.with_borrow_mut(|cache| {
+        let cache = cache.as_mut()?;
+        let res = cache.results.get(&key)?.clone();
+        cache.hits += 1;
+        Some(res)
+    });
+    if let Some(res) = cached {
+        return Ok(res);
+    }
+
+    let res = simulate_theory(data)?;
+    SIM_CACHE.with_borrow_mut(|cache| {
+        if let Some(cache) = cache {
+            cache.results.insert(key, res.clone());
+        }
+    });
+    Ok(res)
+}
+
+/** Parses and runs a query of a batch, a panicking query only fails itself */
+fn run_query(query: serde_json::Value) -> ApiResponse {
+    catch_panic(|| run(query)).into()
+}
+
+/** Runs the queries of a batch, returns their responses and the number of sims taken from the cache */
+fn run_batch(queries: Vec<serde_json::Value>) -> (Vec<ApiResponse>, u32) {
+    // Nested batches share the cache of the outer batch
+    let (nested, outer_hits) = SIM_CACHE.with_borrow_mut(|cache| (cache.is_some(), cache.get_or_insert_default().hits));
+
+    let responses = queries.into_iter().map(run_query).collect();
+
+    let hits = SIM_CACHE.with_borrow_mut(|cache| {
+        let hits = cache.as_ref().map_or(0, |cache| cache.hits - outer_hits);
+        if !nested {
+            *cache = None;
+        }
+        hits
+    });
+    (responses, hits)
+}
+
+/** Runs the queries of a batch, a failing query does not fail the other ones */
+pub fn batch(queries: Vec<serde_json::Value>) -> Vec<ApiResponse> {
+    run_batch(queries).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::api::{config::TheoryType, response::SimResponse};
+
+    fn single_query(strat: &str) -> serde_json::Value {
+        json!({
+            "type": "single",
+            "data": {
+                "theory": "BT",
+                "strat": strat,
+                "sigma": 0,
+                "rho": 100.,
+                "cap": null,
+                "last_strat": null,
+                "time_cap": null,
+                "check_ins": null,
+                "custom_strat": null,
+                "settings": {
+                    "dt": 1.5,
+                    "ddt": 1.0001,
+                    "mf_reset_depth": 0,
+                    "bought_vars_delta": 5,
+                    "sim_all_strats": "all",
+                    "completed_cts": "no",
+                    "show_a23": false,
+                    "show_unofficials": true
+                }
+            }
+        })
+    }
+
+    fn tau_h(response: &ApiResponse) -> f64 {
+        match response {
+            ApiResponse::Success(SimResponse::Single(res)) => res.result.tau_h,
+            res => panic!("Unexpected response {res:?}")
+        }
+    }
+
+    #[test]
+    fn sim_keys() {
+        let data = crate::theories::test_data(TheoryType::BT, "BTd", 100.);
+        assert!(SimKey::new(0, &data) == SimKey::new(0, &data.clone()));
+        assert!(SimKey::new(0, &data) != SimKey::new(1, &data));
+        let mut other = data.clone();
+        other.settings.ddt = 1.0002;
+        assert!(SimKey::new(0, &data) != SimKey::new(0, &other));
+        let mut other = data.clone();
+        other.cap = Some(-data.rho);
+        let mut negated = data.clone();
+        negated.cap = Some(data.rho);
+        assert!(SimKey::new(0, &negated) != SimKey::new(0, &other));
+    }
+
+    #[test]
+    fn shares_identical_sims() {
+        let (responses, hits) = run_batch(vec![
+            single_query("BTd"),
+            single_query("BT"),
+            single_query("BTd"),
+            json!({ "type": "single", "data": {} }),
+            single_query("BTx")
+        ]);
+        assert_eq!(responses.len(), 5);
+        assert_eq!(hits, 1);
+        assert_eq!(tau_h(&responses[0]), tau_h(&responses[2]));
+        assert_ne!(tau_h(&responses[0]), tau_h(&responses[1]));
+        assert!(matches!(&responses[3], ApiResponse::Failure(SimError::Parse { message }) if message.starts_with("Error parsing query")));
+        assert!(matches!(&responses[4], ApiResponse::Failure(SimError::UnknownStrategy { strat, .. }) if strat == "BTx"));
+        assert!(SIM_CACHE.with_borrow(Option::is_none));
+    }
+
+    #[test]
+    fn config_overrides() {
+        let mut overridden = single_query("BTd");
+        overridden["config"] = json!({ "theories": { "BT": { "tau_factor": 0.5 } } });
+        let (responses, hits) = run_batch(vec![single_query("BTd"), overridden, single_query("BTd")]);
+        assert_eq!(hits, 1);
+        assert_eq!(tau_h(&responses[0]), tau_h(&responses[2]));
+        assert_ne!(tau_h(&responses[1]), tau_h(&responses[0]));
+        assert_ne!(current_config().unwrap().theories[&TheoryType::BT].tau_factor, 0.5);
+    }
+
+    #[test]
+    fn nested_batches() {
+        let (responses, hits) = run_batch(vec![
+            single_query("BTd"),
+            json!({ "type": "batch", "data": [single_query("BTd")] })
+        ]);
+        assert_eq!(hits, 1);
+        match &responses[1] {
+            ApiResponse::Success(SimResponse::Batch(nested)) => assert_eq!(tau_h(&nested[0]), tau_h(&responses[0])),
+            res => panic!("Unexpected response {res:?}")
+        }
+        assert!(SIM_CACHE.with_borrow(Option::is_none));
+    }
+}
@@ -0,0 +1,167 @@
+/*!
+ * Side-by-side comparison of strategies
+ *
+ * Each strategy is run as a step sim over the rho range, or chained from the start to the cap.
+ * Step sims start every strategy at the same rho, so their results are compared to the first
+ * strategy. Chains all start at the rho of the query, so only their first publications are
+ * compared; the later ones start at different rho and are listed side by side. Chains are also
+ * compared as a whole, by their tau gain rate and total time.
+ */
+
+use crate::api::{
+    error::SimError,
+    query::{ChainSimQuery, CompareQuery, StepSimQuery},
+    response::{CompareEntry, CompareResponse, CompareRow, PurchaseDivergence}
+};
+use crate::utils::{
+    result::SimResult,
+    var_buy::VarBuy
+};
+
+use super::{chain_sim, step_sim};
+
+/** Returns the first purchase differing between `reference` and `other` */
+fn find_divergence(reference: &[VarBuy], other: &[VarBuy]) -> Option<PurchaseDivergence> {
+    let index = reference.iter()
+        .zip(other)
+        .position(|(a, b)| a.var_name != b.var_name || a.level != b.level)
+        .or_else(|| (reference.len() != other.len()).then(|| reference.len().min(other.len())))?;
+    Some(PurchaseDivergence {
+        index,
+        reference: reference.get(index).cloned(),
+        other: other.get(index).cloned()
+    })
+}
+
+/** Runs the publications of `strat`, with the tau gain rate of the chain in chain mode */
+fn sim_strat(query: &CompareQuery, strat: &str) -> Result<(Vec<SimResult>, Option<f64>), SimError> {
+    match query.step {
+        Some(step) => Ok((step_sim(StepSimQuery {
+            theory: query.theory,
+            strat: strat.to_owned(),
+            sigma: query.sigma,
+            rho: query.rho,
+            cap: query.cap,
+            step,
+            custom_strat: None,
+            settings: query.settings.clone()
+        })?.results, None)),
+        None => {
+            let res = chain_sim(ChainSimQuery {
+                theory: query.theory,
+                strat: strat.to_owned(),
+                sigma: query.sigma,
+                rho: query.rho,
+                cap: Some(query.cap),
+                hard_cap: false,
+                tau_target: None,
+                time_budget: None,
+                check_ins: None,
+                daily_profile: None,
+                custom_strat: None,
+                settings: query.settings.clone()
+            })?;
+            Ok((res.results, Some(res.average_rate)))
+        }
+    }
+}
+
+/** Compares the strategies of `query` to the first one */
+pub fn compare(query: CompareQuery) -> Result<CompareResponse, SimError> {
+    if query.strats.len() < 2 {
+        return Err(SimError::invalid("At least two strategies are needed for a comparison"));
+    }
+    let (results, rates): (Vec<_>, Vec<_>) = query.strats.iter()
+        .map(|strat| sim_strat(&query, strat))
+        .collect::<Result<Vec<_>, SimError>>()?
+        .into_iter()
+        .unzip();
+    let total_time: Vec<f64> = results.iter().map(|res| res.iter().map(|res| res.time).sum()).collect();
+
+    let len = results.iter().map(Vec::len).max().unwrap_or(0);
+    let rows = (0..len).map(|i| {
+        // Step sims start all the strategies at the same rho, chains only their first publication
+        let aligned = query.step.is_some() || i == 0;
+        let reference = results[0].get(i).filter(|_| aligned);
+        CompareRow {
+            rho: reference.map(|reference| reference.last_pub),
+            entries: results.iter().map(|res| res.get(i).map(|res| CompareEntry {
+                tau_h_ratio: reference.map(|reference| res.tau_h / reference.tau_h),
+                time_delta: reference.map(|reference| res.time - reference.time),
+                divergence: reference.and_then(|reference| find_divergence(&reference.bought_vars, &res.bought_vars)),
+                result: res.clone()
+            })).collect()
+        }
+    }).collect();
+
+    let rates: Option<Vec<f64>> = rates.into_iter().collect();
+    Ok(CompareResponse {
+        rate_ratio: rates.as_ref().map(|rates| rates.iter().map(|rate| rate / rates[0]).collect()),
+        time_delta: rates.map(|_| total_time.iter().map(|time| time - total_time[0]).collect()),
+        total_time,
+        strats: query.strats,
+        rows
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::config::TheoryType;
+    use crate::theories::test_data;
+    use crate::utils::lognum::LogNum;
+
+    fn query(strats: &[&str], step: Option<f64>) -> CompareQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = true;
+        CompareQuery {
+            theory: TheoryType::BT,
+            strats: strats.iter().map(|strat| (*strat).to_owned()).collect(),
+            sigma: 0,
+            rho: LogNum::from_log10(100.),
+            cap: LogNum::from_log10(140.),
+            step: step.map(LogNum::from_log10),
+            settings
+        }
+    }
+
+    #[test]
+    fn compares_steps() {
+        let res = compare(query(&["BTd", "BT", "BTd"], Some(10.))).unwrap();
+        assert_eq!(res.rows.len(), 5);
+        for row in &res.rows {
+            let entries: Vec<&CompareEntry> = row.entries.iter().map(|entry| entry.as_ref().unwrap()).collect();
+            assert_eq!(entries[0].tau_h_ratio, Some(1.));
+            assert!(entries[0].divergence.is_none());
+            assert!(entries[1].divergence.is_some());
+            assert_eq!(entries[2].time_delta, Some(0.));
+            assert!(entries[2].divergence.is_none());
+        }
+        assert!(res.rows.iter().all(|row| row.rho.is_some()));
+        assert!(res.rate_ratio.is_none() && res.time_delta.is_none());
+    }
+
+    #[test]
+    fn compares_chains() {
+        let res = compare(query(&["BTd", "BT"], None)).unwrap();
+        assert!(res.rows.len() > 1);
+        assert!(res.rows.iter().all(|row| row.entries[0].is_some() || row.entries[1].is_some()));
+        // Both chains start at the rho of the query
+        let first = &res.rows[0];
+        assert_eq!(first.rho.map(LogNum::log10_f64), Some(100.));
+        let entries: Vec<&CompareEntry> = first.entries.iter().map(|entry| entry.as_ref().unwrap()).collect();
+        assert_eq!(entries[0].tau_h_ratio, Some(1.));
+        assert_eq!(entries[1].tau_h_ratio, Some(entries[1].result.tau_h / entries[0].result.tau_h));
+        assert_eq!(entries[1].time_delta, Some(entries[1].result.time - entries[0].result.time));
+        assert!(entries[1].divergence.is_some());
+        assert!(res.rows[1..].iter().all(|row| row.rho.is_none()));
+        assert!(res.rows[1..].iter().flat_map(|row| row.entries.iter().flatten())
+            .all(|entry| entry.tau_h_ratio.is_none() && entry.time_delta.is_none() && entry.divergence.is_none()));
+        assert!(res.total_time.iter().all(|time| *time > 0.));
+        let rate_ratio = res.rate_ratio.unwrap();
+        assert_eq!(rate_ratio[0], 1.);
+        assert!(rate_ratio[1] > 0.);
+        assert_eq!(res.time_delta, Some(vec![0., res.total_time[1] - res.total_time[0]]));
+        assert!(compare(query(&["BTd"], None)).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+ * Convergence of sims with the tick settings
+ *
+ * A sim is rerun with a ladder of dt/ddt settings and compared to the same sim with the exact
+ * game tick (0.1s ticks, dt = 0.15 and ddt = 1). The cost of settings is the number of ticks
+ * they simulated. The exact game tick simulates every tick, so its sim is refused when it would
+ * take too many of them.
+ */
+
+use crate::api::{
+    error::SimError,
+    query::{ConvergenceQuery, SingleSimQuery, TickSettings},
+    response::{ConvergenceEntry, ConvergenceResponse}
+};
+use crate::utils::result::SimResult;
+
+use super::single_sim;
+
+/** Tick settings of the game, with one tick every 0.1s */
+const EXACT_TICK: TickSettings = TickSettings { dt: 0.15, ddt: 1. };
+/** Ladder used when the query has none, from the fastest to the most accurate settings */
+const DEFAULT_LADDER: [TickSettings; 5] = [
+    TickSettings { dt: 5., ddt: 1.001 },
+    TickSettings { dt: 3., ddt: 1.0003 },
+    TickSettings { dt: 1.5, ddt: 1.0001 },
+    TickSettings { dt: 0.75, ddt: 1.00003 },
+    TickSettings { dt: 0.3, ddt: 1.00001 }
+];
+/** Default relative tau/h error accepted for the recommended settings */
+const DEFAULT_TOLERANCE: f64 = 1e-3;
+/** Maximum number of ticks of the exact game tick sim, about 280 hours of game time */
+const MAX_EXACT_TICKS: f64 = 1e7;
+
+/** Returns the number of ticks needed to reach `time` with the tick settings */
+fn estimate_ticks(tick_settings: TickSettings, time: f64) -> f64 {
+    // Each tick advances the time by dt / 1.5, and dt grows by ddt every tick
+    let ticks = time * 1.5 / tick_settings.dt;
+    if tick_settings.ddt == 1. {
+        ticks
+    } else {
+        (ticks * (tick_settings.ddt - 1.)).ln_1p() / tick_settings.ddt.ln()
+    }
+}
+
+/** Refuses the exact game tick sim of a publication taking `time` seconds if it needs too many ticks */
+fn check_exact_ticks(time: f64) -> Result<(), SimError> {
+    if estimate_ticks(EXACT_TICK, time) > MAX_EXACT_TICKS {
+        return Err(SimError::limit(format!(
+            "The sim with the exact game tick would take more than {MAX_EXACT_TICKS:e} ticks"
+        )));
+    }
+    Ok(())
+}
+
+/** Runs the sim of `query` with the tick settings */
+fn sim_with(query: &ConvergenceQuery, tick_settings: TickSettings) -> Result<SimResult, SimError> {
+    let mut settings = query.settings.clone();
+    settings.dt = tick_settings.dt;
+    settings.ddt = tick_settings.ddt;
+    Ok(single_sim(SingleSimQuery {
+        theory: query.theory,
+        strat: query.strat.clone(),
+        sigma: query.sigma,
+        rho: query.rho,
+        cap: None,
+        last_strat: query.last_strat.clone(),
+        time_cap: None,
+        check_ins: None,
+        autobuy: false,
+        custom_strat: None,
+        settings
+    })?.result)
+}
+
+/** Compares the sims of the ladder of `query` to the exact game tick */
+pub fn convergence(query: ConvergenceQuery) -> Result<ConvergenceResponse, SimError> {
+    let ladder = query.ladder.clone().unwrap_or_else(|| DEFAULT_LADDER.to_vec());
+    if let Some(tick_settings) = ladder.iter().find(|tick_settings| !(tick_settings.dt > 0. && tick_settings.ddt >= 1.)) {
+        return Err(SimError::invalid(format!("Invalid tick settings: dt = {}, ddt = {}", tick_settings.dt, tick_settings.ddt)));
+    }
+    let tolerance = query.tolerance.unwrap_or(DEFAULT_TOLERANCE);
+    if tolerance.is_nan() || tolerance < 0. {
+        return Err(SimError::invalid("The error tolerance must be positive"));
+    }
+
+    let results = ladder.iter()
+        .map(|&tick_settings| if tick_settings == EXACT_TICK { Ok(None) } else { sim_with(&query, tick_settings).map(Some) })
+        .collect::<Result<Vec<_>, SimError>>()?;
+    // The publication time of the cheapest sim of the ladder (fewest ticks) bounds the length of the exact game tick sim
+    check_exact_ticks(match results.iter().flatten().min_by_key(|result| result.ticks) {
+        Some(result) => result.time,
+        None => sim_with(&query, DEFAULT_LADDER[0])?.time
+    })?;
+
+    let reference = sim_with(&query, EXACT_TICK)?;
+    let entries = ladder.into_iter()
+        .zip(results)
+        .map(|(tick_settings, result)| {
+            let result = result.unwrap_or_else(|| reference.clone());
+            Ok(ConvergenceEntry {
+                tick_settings,
+                ticks: result.ticks,
+                tau_h_error: (result.tau_h - reference.tau_h).abs() / reference.tau_h.abs(),
+                time_error: (result.time - reference.time).abs() / reference.time,
+                rho_error: (result.pub_rho.log10_f64() - reference.pub_rho.log10_f64()).abs(),
+                result
+            })
+        })
+        .collect::<Result<Vec<_>, SimError>>()?;
+
+    // The exact game tick is always within the tolerance
+    let recommended = entries.iter()
+        .filter(|entry| entry.tau_h_error <= tolerance)
+        .min_by_key(|entry| entry.ticks)
+        .map_or(EXACT_TICK, |entry| entry.tick_settings);
+
+    Ok(ConvergenceResponse { reference, entries, recommended })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::config::TheoryType;
+    use crate::theories::test_data;
+    use crate::utils::lognum::LogNum;
+
+    fn query(ladder: Option<Vec<TickSettings>>, tolerance: Option<f64>) -> ConvergenceQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = true;
+        ConvergenceQuery {
+            theory: TheoryType::BT,
+            strat: "BTd".to_owned(),
+            sigma: 0,
+            rho: LogNum::from_log10(100.),
+            last_strat: None,
+            ladder,
+            tolerance,
+            settings
+        }
+    }
+
+    #[test]
+    fn ticks_estimate() {
+        assert!((estimate_ticks(EXACT_TICK, 3600.) - 36000.).abs() < 1e-6);
+        let tick_settings = TickSettings { dt: 1.5, ddt: 1.0001 };
+        let ticks = estimate_ticks(tick_settings, 3600.);
+        let time = (tick_settings.ddt.powf(ticks) - 1.) / (tick_settings.ddt - 1.);
+        assert!((time - 3600.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn recommends_cheapest_accurate_settings() {
+        let res = convergence(query(None, Some(0.01))).unwrap();
+        assert_eq!(res.entries.len(), DEFAULT_LADDER.len());
+        let recommended = res.entries.iter().find(|entry| entry.tick_settings == res.recommended).unwrap();
+        assert!(recommended.tau_h_error <= 0.01);
+        assert!(res.entries.iter().all(|entry| entry.tau_h_error > 0.01 || entry.ticks >= recommended.ticks));
+
+        let res = convergence(query(Some(vec![EXACT_TICK]), Some(0.))).unwrap();
+        assert_eq!(res.entries[0].tau_h_error, 0.);
+        assert_eq!(res.entries[0].ticks, res.reference.ticks);
+        assert_eq!(res.recommended, EXACT_TICK);
+    }
+
+    #[test]
+    fn limits_exact_tick_sim() {
+        assert!(check_exact_ticks(100. * 3600.).is_ok());
+        assert!(matches!(check_exact_ticks(1000. * 3600.), Err(SimError::LimitExceeded { .. })));
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert!(convergence(query(Some(vec![TickSettings { dt: 0., ddt: 1. }]), None)).is_err());
+        assert!(convergence(query(Some(vec![TickSettings { dt: 1.5, ddt: 0.9 }]), None)).is_err());
+        assert!(convergence(query(None, Some(-1.))).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+/*!
+ * Location of the points where a strategy overtakes another one
+ *
+ * The rho interval is first sampled with a fixed step, then every sign change of the tau/h
+ * difference between the two strategies is refined by bisection.
+ */
+
+use num::Float;
+
+use crate::api::{
+    error::SimError,
+    query::{CrossoverQuery, SingleSimQuery},
+    response::{CrossoverPoint, CrossoverResponse, CrossoverSide}
+};
+use crate::utils::lognum::LogNum;
+
+use super::single_sim;
+
+/** Default width (log10 of rho) under which a crossover is considered located */
+const DEFAULT_PRECISION: f64 = 0.1;
+/** Maximum number of rho values of the coarse sampling */
+const MAX_GRID_POINTS: f64 = 1000.;
+
+/** Sims both strategies at `rho` */
+fn sim_side(query: &CrossoverQuery, rho: LogNum) -> Result<CrossoverSide, SimError> {
+    let mut tau_h = [0.; 2];
+    for (i, strat) in query.strats.iter().enumerate() {
+        tau_h[i] = single_sim(SingleSimQuery {
+            theory: query.theory,
+            strat: strat.clone(),
+            sigma: query.sigma,
+            rho,
+            cap: None,
+            last_strat: None,
+            time_cap: None,
+            check_ins: None,
+            autobuy: false,
+            custom_strat: None,
+            settings: query.settings.clone()
+        })?.result.tau_h;
+    }
+    Ok(CrossoverSide { rho, tau_h })
+}
+
+/** Returns true if the first strategy is better, ties count as the second strategy being better */
+fn first_is_better(side: &CrossoverSide) -> bool {
+    side.tau_h[0] > side.tau_h[1]
+}
+
+/** Narrows a crossover bracketed by `below` and `above` down to `precision` */
+fn bisect(query: &CrossoverQuery, mut below: CrossoverSide, mut above: CrossoverSide, precision: f64) -> Result<CrossoverPoint, SimError> {
+    while above.rho.log10_f64() - below.rho.log10_f64() > precision {
+        let mid = sim_side(query, LogNum::from_log10((below.rho.log10_f64() + above.rho.log10_f64()) / 2.))?;
+        if first_is_better(&mid) == first_is_better(&below) {
+            below = mid;
+        } else {
+            above = mid;
+        }
+    }
+    Ok(CrossoverPoint {
+        rho: LogNum::from_log10((below.rho.log10_f64() + above.rho.log10_f64()) / 2.),
+        below,
+        above
+    })
+}
+
+/** Finds the crossovers of the tau/h curves of the two strategies of `query` */
+pub fn find_crossovers(query: CrossoverQuery) -> Result<CrossoverResponse, SimError> {
+    if query.step <= LogNum::from(1.) {
+        return Err(SimError::invalid("The rho step must be greater than 1"));
+    }
+    if query.rho > query.cap {
+        return Err(SimError::invalid("The starting rho must not be greater than the cap"));
+    }
+    let grid_points = ((query.cap.log10_f64() - query.rho.log10_f64()) / query.step.log10_f64()).ceil() + 1.;
+    if grid_points > MAX_GRID_POINTS {
+        return Err(SimError::limit(format!("A crossover search cannot sample more than {MAX_GRID_POINTS} rho values")));
+    }
+    let precision = query.precision.unwrap_or(DEFAULT_PRECISION);
+    if precision <= 0. {
+        return Err(SimError::invalid("The crossover precision must be positive"));
+    }
+
+    let mut crossovers = Vec::new();
+    let mut prev = sim_side(&query, query.rho)?;
+    let mut rho = query.rho;
+    while rho < query.cap {
+        rho = (rho * query.step).min(query.cap);
+        let side = sim_side(&query, rho)?;
+        if first_is_better(&side) != first_is_better(&prev) {
+            crossovers.push(bisect(&query, prev, side.clone(), precision)?);
+        }
+        prev = side;
+    }
+
+    Ok(CrossoverResponse { strats: query.strats, crossovers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::config::TheoryType;
+    use crate::theories::test_data;
+
+    fn query(strats: [&str; 2], precision: Option<f64>) -> CrossoverQuery {
+        CrossoverQuery {
+            theory: TheoryType::MF,
+            strats: strats.map(str::to_owned),
+            sigma: 0,
+            rho: LogNum::from_log10(20.),
+            cap: LogNum::from_log10(60.),
+            step: LogNum::from_log10(20.),
+            precision,
+            settings: test_data(TheoryType::MF, "MF", 0.).settings
+        }
+    }
+
+    #[test]
+    fn crossovers_are_bracketed() {
+        // MFd is better at e20 and e60, MFd2 at e40
+        let res = find_crossovers(query(["MFd", "MFd2"], Some(2.))).unwrap();
+        assert_eq!(res.crossovers.len(), 2);
+        for crossover in &res.crossovers {
+            assert!(crossover.above.rho.log10_f64() - crossover.below.rho.log10_f64() <= 2.);
+            assert_ne!(first_is_better(&crossover.below), first_is_better(&crossover.above));
+        }
+    }
+
+    #[test]
+    fn same_strat_has_no_crossover() {
+        assert!(find_crossovers(query(["MFd", "MFd"], None)).unwrap().crossovers.is_empty());
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert!(find_crossovers(query(["MFd", "MFd2"], Some(0.))).is_err());
+        assert!(find_crossovers(query(["MFd", "MFx"], None)).is_err());
+        let reversed = CrossoverQuery { cap: LogNum::from_log10(10.), ..query(["MFd", "MFd2"], None) };
+        assert!(matches!(find_crossovers(reversed), Err(SimError::InvalidQuery { .. })));
+        let dense = CrossoverQuery { step: LogNum::from_log10(0.001), ..query(["MFd", "MFd2"], None) };
+        assert!(matches!(find_crossovers(dense), Err(SimError::LimitExceeded { .. })));
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+ * Metadata of the theories, from data.json and from the theory sims
+ */
+
+use crate::api::{
+    config::{Config, TheoryType, current_config},
+    error::SimError,
+    query::DescribeQuery,
+    response::{DescribeResponse, StratDescription, TheoryDescription, TheoryModel, VariableDescription}
+};
+use crate::theories::{
+    is_unofficial,
+    theory::{TheoryBase, TheoryData},
+    theory_base
+};
+use crate::utils::{
+    lognum::{self, LogNum},
+    settings::SimSettings
+};
+
+/** Creates the data of a sim of `theory` from `rho` with default settings */
+fn describe_data(theory: TheoryType, strat: &str, rho: LogNum) -> TheoryData {
+    TheoryData {
+        theory,
+        sigma: 0,
+        rho,
+        strat: strat.to_owned(),
+        cap: None,
+        time_cap: None,
+        check_ins: None,
+        autobuy: false,
+        resume: None,
+        custom_strat: None,
+        settings: SimSettings::default()
+    }
+}
+
+fn theory_model(base: TheoryBase) -> TheoryModel {
+    TheoryModel {
+        pub_unlock: base.pub_unlock,
+        milestone_unlock_steps: (base.milestone_unlock_steps > 0.).then_some(base.milestone_unlock_steps),
+        milestone_unlocks: base.milestone_unlocks,
+        milestones_max: base.milestones_max,
+        variables: base.variables.iter().map(|var| VariableDescription {
+            name: var.name.clone(),
+            cost: var.cost_model.describe(),
+            value: var.value_model.describe()
+        }).collect()
+    }
+}
+
+/** Describes `theory`, the milestone maxima are the ones of a publication from `rho` */
+fn describe_theory(config: &Config, theory: TheoryType, rho: Option<LogNum>) -> Result<TheoryDescription, SimError> {
+    let theory_config = config.theories.get(&theory)
+        .ok_or_else(|| SimError::no_theory_config(theory))?;
+    let strats = theory_config.strats.iter()
+        .map(|(name, strat)| StratDescription {
+            name: name.clone(),
+            filter_condition: strat.strat_filter_condition.clone(),
+            forced_condition: strat.forced_condition.clone(),
+            ui_visible: strat.ui_visible != Some(false)
+        })
+        .collect();
+
+    let model = match theory_config.strats.keys().next() {
+        Some(strat) => {
+            let base = theory_base(&describe_data(theory, strat, rho.unwrap_or(lognum::ONE)))?;
+            // Without rho, the sim is created again after the last milestone unlock
+            let base = match (base, rho) {
+                (Some(base), None) => {
+                    let last_unlock = if base.milestone_unlock_steps > 0. {
+                        base.milestone_unlock_steps * base.milestones_max.iter().sum::<i32>() as f64
+                    } else {
+                        base.milestone_unlocks.last().copied().unwrap_or(0.)
+                    };
+                    theory_base(&describe_data(theory, strat, LogNum::from_log10(last_unlock)))?
+                },
+                (base, _) => base
+            };
+            base.map(theory_model)
+        },
+        None => None
+    };
+
+    Ok(TheoryDescription {
+        theory,
+        tau_factor: theory_config.tau_factor,
+        ui_visible: theory_config.ui_visible != Some(false),
+        unofficial: is_unofficial(theory),
+        strats,
+        model
+    })
+}
+
+/** Describes the theories of `query` */
+pub fn describe(query: DescribeQuery) -> Result<DescribeResponse, SimError> {
+    let config = current_config()?;
+    let theories = match query.theory {
+        Some(theory) => vec![theory],
+        None => (0..).map_while(|i| TheoryType::try_from(i).ok()).collect()
+    };
+
+    Ok(DescribeResponse {
+        config_version: config.version.clone(),
+        strat_categories: config.strat_categories.clone(),
+        theories: theories.into_iter()
+            .map(|theory| describe_theory(&config, theory, query.rho))
+            .collect::<Result<Vec<_>, SimError>>()?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{cost::CostModel, value::ValueModel};
+
+    #[test]
+    fn describes_every_theory() {
+        let res = describe(DescribeQuery { theory: None, rho: None }).unwrap();
+        assert_eq!(res.theories.len(), TheoryType::TC as usize + 1);
+        for description in &res.theories {
+            assert!(!description.strats.is_empty());
+            assert_eq!(description.model.is_some(), matches!(description.theory, TheoryType::MF | TheoryType::BaP | TheoryType::BT | TheoryType::TC));
+        }
+
+        let bt = &res.theories[TheoryType::BT as usize];
+        assert!(bt.unofficial && !bt.ui_visible);
+        let model = bt.model.as_ref().unwrap();
+        assert_eq!(model.pub_unlock, 7.);
+        assert_eq!(model.milestones_max, vec![3, 3, 6, 1]);
+        let names: Vec<&str> = model.variables.iter().map(|var| var.name.as_str()).collect();
+        assert_eq!(names, ["tai", "rao", "tay"]);
+        assert!(matches!(&model.variables[0].cost, CostModel::FirstFree { model } if matches!(**model, CostModel::Exponential { .. })));
+        assert!(matches!(model.variables[1].value, ValueModel::Exponential { .. }));
+    }
+
+    #[test]
+    fn milestones_depend_on_rho() {
+        let model = |rho: Option<f64>| describe(DescribeQuery { theory: Some(TheoryType::BaP), rho: rho.map(LogNum::from_log10) })
+            .unwrap().theories.remove(0).model.unwrap();
+        let early = model(Some(10.));
+        let late = model(None);
+        assert!(early.milestones_max.iter().sum::<i32>() < late.milestones_max.iter().sum::<i32>());
+        assert_eq!(late.variables.len(), 12);
+    }
+}
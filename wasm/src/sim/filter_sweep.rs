@@ -0,0 +1,334 @@
+/*!
+ * Generation of the strategy filter conditions of data.json from simulation sweeps
+ *
+ * Every candidate strategy is simulated on a grid of rho. In each strategy category, a strategy is
+ * kept at a grid point if its tau/h is within the tolerance of the best candidate of the category.
+ * The filter condition of a strategy is then written from the grid points where it is kept, with
+ * thresholds placed at the first grid point of each change.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::api::{
+    config::{Config, ConfigStrat, current_config},
+    error::SimError,
+    query::{FilterSweepQuery, SingleSimQuery},
+    response::{FilterSweepResponse, StratFilterDiff}
+};
+use crate::utils::{expr, lognum::LogNum};
+
+use super::single_sim;
+use super::strats::{CATEGORY_NAMES, StratConditionContext, eval_condition};
+
+/** Order in which categories are written in conditions, as done in data.json */
+const CATEGORY_WRITE_ORDER: [usize; 4] = [1, 0, 3, 2];
+/** Maximum number of sims of a sweep, grid points times candidates */
+const MAX_SIMS: usize = 2000;
+
+/** Evaluates the filter and forced conditions of a strategy */
+fn eval_conditions(config: &Config, strat: &ConfigStrat, rho: LogNum, category: &str) -> Result<(bool, bool), SimError> {
+    let ctx = StratConditionContext::new(config, rho, category, "");
+    let filter = eval_condition(&strat.strat_filter_condition, &ctx)?;
+    let forced = match &strat.forced_condition {
+        Some(cond) => eval_condition(cond, &ctx)?,
+        None => true
+    };
+    Ok((filter, forced))
+}
+
+/** Formats a rho threshold (log10) */
+fn format_rho(rho: LogNum) -> String {
+    format!("{}", (rho.log10_f64() * 100.).round() / 100.)
+}
+
+/**
+ * Writes the condition on rho matching the grid points set in `kept`
+ *
+ * Returns None if the strategy is kept everywhere.
+ */
+fn rho_condition(kept: &[bool], rhos: &[LogNum]) -> Option<String> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < kept.len() {
+        if !kept[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < kept.len() && kept[i] {
+            i += 1;
+        }
+        let mut bounds = Vec::new();
+        if start > 0 {
+            bounds.push(format!("rho >= {}", format_rho(rhos[start])));
+        }
+        if i < kept.len() {
+            bounds.push(format!("rho < {}", format_rho(rhos[i])));
+        }
+        ranges.push(bounds.join(" && "));
+    }
+
+    match ranges.as_slice() {
+        [range] if range.is_empty() => None,
+        [range] => Some(range.clone()),
+        _ => Some(ranges.iter().map(|range| format!("({range})")).collect::<Vec<String>>().join(" || "))
+    }
+}
+
+/** Writes a filter condition from the grid points where a strategy is kept in each category */
+fn write_condition(kept: &[Vec<bool>; 4], rhos: &[LogNum]) -> String {
+    // Categories sharing the same rho condition are written together
+    let mut groups: IndexMap<Option<String>, Vec<&str>> = IndexMap::new();
+    for i in CATEGORY_WRITE_ORDER {
+        if kept[i].iter().any(|k| *k) {
+            groups.entry(rho_condition(&kept[i], rhos)).or_default().push(CATEGORY_NAMES[i]);
+        }
+    }
+    if groups.is_empty() {
+        return "false".to_owned();
+    }
+
+    let multiple_groups = groups.len() > 1;
+    groups.into_iter()
+        .map(|(rho_cond, categories)| {
+            let categories_cond = if categories.len() > 1 && (multiple_groups || rho_cond.is_some()) {
+                format!("({})", categories.join(" || "))
+            } else {
+                categories.join(" || ")
+            };
+            match rho_cond {
+                None => categories_cond,
+                Some(rho_cond) => {
+                    let rho_cond = if rho_cond.contains(" || ") { format!("({rho_cond})") } else { rho_cond };
+                    if multiple_groups {
+                        format!("({categories_cond} && {rho_cond})")
+                    } else {
+                        format!("{categories_cond} && {rho_cond}")
+                    }
+                }
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" || ")
+}
+
+/** Returns the grid of rho values of the sweep */
+fn rho_grid(query: &FilterSweepQuery) -> Vec<LogNum> {
+    let mut rhos = Vec::new();
+    let mut rho = query.rho;
+    while rho < query.cap * LogNum::from(1.001) {
+        rhos.push(rho);
+        rho *= query.step;
+    }
+    rhos
+}
+
+/** Sweeps the strategies of a theory and proposes new filter conditions */
+pub fn filter_sweep(query: FilterSweepQuery) -> Result<FilterSweepResponse, SimError> {
+    let config = current_config()?;
+    let theory_config = config.theories.get(&query.theory)
+        .ok_or_else(|| SimError::no_theory_config(query.theory))?;
+    if query.step <= LogNum::from(1.) {
+        return Err(SimError::invalid("The rho step must be greater than 1"));
+    }
+    let tolerance = query.tolerance.unwrap_or(0.);
+    let limit = || SimError::limit(format!("A filter sweep cannot run more than {MAX_SIMS} sims"));
+    if (query.cap.log10_f64() - query.rho.log10_f64()) / query.step.log10_f64() >= MAX_SIMS as f64 {
+        return Err(limit());
+    }
+    let rhos = rho_grid(&query);
+
+    // Candidates of each category, in the order of strat_categories, taken from the config if not given
+    let mut candidates: Vec<Vec<String>> = Vec::new();
+    for category in config.strat_categories.iter().take(4) {
+        let strats = match query.candidates.as_ref().and_then(|candidates| candidates.get(category)) {
+            Some(strats) => strats.clone(),
+            None => {
+                let mut strats = Vec::new();
+                for (name, strat) in &theory_config.strats {
+                    let mut used = false;
+                    for &rho in &rhos {
+                        used |= eval_conditions(&config, strat, rho, category)?.0;
+                    }
+                    if used {
+                        strats.push(name.clone());
+                    }
+                }
+                strats
+            }
+        };
+        if let Some(strat) = strats.iter().find(|strat| !theory_config.strats.contains_key(*strat)) {
+            return Err(SimError::unknown_strategy(query.theory, strat));
+        }
+        candidates.push(strats);
+    }
+    let candidate_count = candidates.iter().flatten().collect::<HashSet<_>>().len();
+    if rhos.len() * candidate_count > MAX_SIMS {
+        return Err(limit());
+    }
+
+    // tau/h of every candidate at every grid point, None when its forced condition prevents it
+    let mut tau_h: HashMap<&str, Vec<f64>> = HashMap::new();
+    for strat in candidates.iter().flatten() {
+        if tau_h.contains_key(strat.as_str()) {
+            continue;
+        }
+        let mut values = Vec::new();
+        for &rho in &rhos {
+            values.push(single_sim(SingleSimQuery {
+                theory: query.theory,
+                strat: strat.clone(),
+                sigma: query.sigma,
+                rho,
+                cap: None,
+                last_strat: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                custom_strat: None,
+                settings: query.settings.clone()
+            })?.result.tau_h);
+        }
+        tau_h.insert(strat, values);
+    }
+
+    let mut kept: IndexMap<&str, [Vec<bool>; 4]> = theory_config.strats.keys()
+        .map(|name| (name.as_str(), std::array::from_fn(|_| vec![false; rhos.len()])))
+        .collect();
+    for (c, category) in config.strat_categories.iter().take(4).enumerate() {
+        for (r, &rho) in rhos.iter().enumerate() {
+            let mut allowed = Vec::new();
+            for strat in &candidates[c] {
+                if eval_conditions(&config, &theory_config.strats[strat], rho, category)?.1 {
+                    allowed.push((strat.as_str(), tau_h[strat.as_str()][r]));
+                }
+            }
+            let best = allowed.iter().map(|(_, tau_h)| *tau_h).fold(f64::NEG_INFINITY, f64::max);
+            for (strat, tau_h) in allowed {
+                if tau_h >= best - tolerance * best.abs() {
+                    kept[strat][c][r] = true;
+                }
+            }
+        }
+    }
+
+    let mut conditions = Vec::new();
+    for (name, strat) in &theory_config.strats {
+        let proposed = write_condition(&kept[name.as_str()], &rhos);
+        // The proposed conditions are written by the sweep, failing to use them is a bug
+        let internal = |message: String| SimError::Internal { message: format!("Invalid proposed condition {proposed}: {message}") };
+        let proposed_expr = expr::parse(&proposed).map_err(internal)?;
+        let current_expr = expr::parse(&strat.strat_filter_condition).map_err(SimError::config)?;
+        let mut changed = false;
+        for category in config.strat_categories.iter().take(4) {
+            for &rho in &rhos {
+                let ctx = StratConditionContext::new(&config, rho, category, "");
+                changed |= proposed_expr.eval_bool(&ctx).map_err(internal)? != current_expr.eval_bool(&ctx).map_err(SimError::config)?;
+            }
+        }
+        conditions.push(StratFilterDiff {
+            strat: name.clone(),
+            current: strat.strat_filter_condition.clone(),
+            proposed,
+            changed
+        });
+    }
+
+    Ok(FilterSweepResponse { theory: query.theory, conditions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{config::TheoryType, query::SimQuery};
+    use crate::theories::test_data;
+
+    fn grid(points: &[f64]) -> Vec<LogNum> {
+        points.iter().map(|&rho| LogNum::from_log10(rho)).collect()
+    }
+
+    #[test]
+    fn written_conditions() {
+        let rhos = grid(&[10., 25., 50., 100.]);
+        let none = vec![false; 4];
+        let all = vec![true; 4];
+        assert_eq!(write_condition(&[none.clone(), none.clone(), none.clone(), none.clone()], &rhos), "false");
+        assert_eq!(write_condition(&[all.clone(), all.clone(), none.clone(), none.clone()], &rhos), "Active || Very-Active");
+        assert_eq!(
+            write_condition(&[none.clone(), none.clone(), vec![true, true, false, false], vec![false, true, true, true]], &rhos),
+            "(Idle && rho >= 25) || (Semi-Idle && rho < 50)"
+        );
+        assert_eq!(
+            write_condition(&[all.clone(), vec![true, false, false, true], none.clone(), none], &rhos),
+            "(Active && ((rho < 25) || (rho >= 100))) || Very-Active"
+        );
+    }
+
+    #[test]
+    fn written_conditions_parse() {
+        let rhos = grid(&[10., 25., 50.]);
+        let cond = write_condition(&[vec![true, false, true], vec![false, true, true], vec![true; 3], vec![false, false, true]], &rhos);
+        assert!(expr::parse(&cond).is_ok());
+    }
+
+    #[test]
+    fn query_tag() {
+        let settings = serde_json::json!({
+            "dt": 1.5, "ddt": 1.0001, "mf_reset_depth": 0, "bought_vars_delta": 5, "sim_all_strats": "all",
+            "completed_cts": "no", "show_a23": false, "show_unofficials": false
+        });
+        let query = serde_json::from_value(serde_json::json!({
+            "type": "filter_sweep",
+            "data": { "theory": "MF", "sigma": 0, "rho": 20, "cap": 40, "step": 20, "settings": settings }
+        })).unwrap();
+        assert!(matches!(query, SimQuery::FilterSweep(_)));
+    }
+
+    #[test]
+    fn sweep_keeps_best_strat() {
+        let strats = vec!["MFd2Coast".to_owned(), "MFdCoast".to_owned()];
+        let res = filter_sweep(FilterSweepQuery {
+            theory: TheoryType::MF,
+            sigma: 0,
+            rho: LogNum::from_log10(20.),
+            cap: LogNum::from_log10(40.),
+            step: LogNum::from_log10(20.),
+            tolerance: Some(0.001),
+            // Best Idle is missing and takes its candidates from the config
+            candidates: Some(IndexMap::from([
+                ("Best Overall".to_owned(), Vec::new()),
+                ("Best Active".to_owned(), strats),
+                ("Best Semi-Idle".to_owned(), Vec::new())
+            ])),
+            settings: test_data(TheoryType::MF, "MF", 0.).settings
+        }).unwrap();
+        let condition = |strat: &str| res.conditions.iter().find(|diff| diff.strat == strat).unwrap();
+        // MFdCoast is better at e20 and MFd2Coast at e40, each one is only kept where it is the best
+        assert_eq!(condition("MFdCoast").proposed, "Active && rho < 40");
+        assert_eq!(condition("MFd2Coast").proposed, "Active && rho >= 40");
+        assert_eq!(condition("MFRCCoast").proposed, "Idle");
+        assert_eq!(condition("MF").proposed, "false");
+        assert!(!condition("MF").changed);
+        assert!(condition("MFdCoast").changed);
+    }
+
+    #[test]
+    fn sweep_limits() {
+        let query = |step: f64| FilterSweepQuery {
+            theory: TheoryType::MF,
+            sigma: 0,
+            rho: LogNum::from_log10(20.),
+            cap: LogNum::from_log10(40.),
+            step: LogNum::from_log10(step),
+            tolerance: None,
+            candidates: None,
+            settings: test_data(TheoryType::MF, "MF", 0.).settings
+        };
+        // 2001 grid points
+        assert!(matches!(filter_sweep(query(0.01)), Err(SimError::LimitExceeded { .. })));
+        // 201 grid points with every strategy of the categories of MF
+        assert!(matches!(filter_sweep(query(0.1)), Err(SimError::LimitExceeded { .. })));
+    }
+}
@@ -1,51 +1,196 @@
-use crate::CONFIG;
+mod strats;
+mod tune;
+mod crossover;
+mod compare;
+mod planner;
+mod convergence;
+mod describe;
+mod batch;
+mod filter_sweep;
+
+use std::sync::Arc;
+
+use num::Float;
+
 use crate::api::{
-    config::TheoryType,
+    config::{Config, TheoryType, current_config, with_config_overrides},
+    error::SimError,
+    input::parse_query,
+    schema::API_VERSION,
     query::*, 
     response::*
 };
+use crate::theories::{
+    cts::rz_zeros::rz_zeros,
+    custom_strat::CustomStrat,
+    generate_pub_table,
+    is_supported,
+    is_unofficial,
+    multiplier::MultiplierModel,
+    theory::TheoryData
+};
 use crate::utils::{
     lognum::{self,LogNum},
     result::*,
+    schedule::DailyProfile,
     settings::*
 };
 
+/** Maximum number of publications simulated by a chain or step sim */
+const MAX_PUBLICATIONS: usize = 1000;
+
+/** Returns the strategies of a strategy category, or `strat` itself if it is not a category */
+fn expand_strat(theory: TheoryType, rho: LogNum, strat: String, last_strat: Option<&str>) -> Result<Vec<String>, SimError> {
+    let config = current_config()?;
+    if config.strat_categories.contains(&strat) {
+        strats::get_strats(&config, theory, rho, &strat, last_strat.unwrap_or(""))
+    } else {
+        Ok(vec![strat])
+    }
+}
+
+/** Compiles the custom strategy of a query, once for all the sims of the query */
+fn compile_custom_strat(custom_strat: Option<&CustomStratQuery>) -> Result<Option<Arc<CustomStrat>>, SimError> {
+    Ok(custom_strat.map(CustomStrat::compile).transpose()?.map(Arc::new))
+}
+
+fn single_sim(query: SingleSimQuery) -> Result<SingleSimResponse, SimError> {
+    let custom_strat = compile_custom_strat(query.custom_strat.as_ref())?;
+    compiled_single_sim(query, custom_strat)
+}
+
+/** Runs a single sim with its compiled custom strategy, the custom strategy of the query is ignored */
+fn compiled_single_sim(query: SingleSimQuery, custom_strat: Option<Arc<CustomStrat>>) -> Result<SingleSimResponse, SimError> {
+    let strats = expand_strat(query.theory, query.rho, query.strat, query.last_strat.as_deref())?;
+    if let Some(check_ins) = &query.check_ins {
+        check_ins.validate().map_err(SimError::invalid)?;
+    }
+
+    let mut best_res: Option<SimResult> = None;
+    for strat in strats {
+        let res = batch::simulate_cached(TheoryData {
+            theory: query.theory,
+            sigma: query.sigma,
+            rho: query.rho,
+            strat,
+            cap: query.cap,
+            time_cap: query.time_cap,
+            check_ins: query.check_ins.clone(),
+            autobuy: query.autobuy,
+            resume: None,
+            custom_strat: custom_strat.clone(),
+            settings: query.settings.clone()
+        })?;
+        keep_best_result(&mut best_res, res);
+    }
+
+    Ok(SingleSimResponse { result: best_res.unwrap_or_default() })
+}
+
+fn resume_sim(query: ResumeQuery) -> Result<ResumeResponse, SimError> {
+    let strats = expand_strat(query.theory, query.last_pub, query.strat, query.last_strat.as_deref())?;
 
-fn single_sim(query: SingleSimQuery) -> Result<SingleSimResponse, String> {
-    Ok(SingleSimResponse { result: SimResult::default() })
+    let mut best_res: Option<SimResult> = None;
+    for strat in strats {
+        let res = batch::simulate_cached(TheoryData {
+            theory: query.theory,
+            sigma: query.sigma,
+            rho: query.last_pub,
+            strat,
+            cap: None,
+            time_cap: None,
+            check_ins: None,
+            autobuy: false,
+            resume: Some(query.state.clone()),
+            custom_strat: None,
+            settings: query.settings.clone()
+        })?;
+        keep_best_result(&mut best_res, res);
+    }
+    let best_res = best_res.unwrap_or_default();
+
+    Ok(ResumeResponse {
+        remaining_time: (best_res.time - query.state.t).max(0.),
+        result: best_res
+    })
 }
 
-fn chain_sim(query: ChainSimQuery) -> Result<ChainSimResponse, String> {
+/** Checks the windows of a daily profile and their categories */
+fn validate_profile(config: &Config, profile: &DailyProfile) -> Result<(), SimError> {
+    profile.validate().map_err(SimError::invalid)?;
+    for window in &profile.windows {
+        strats::category_name(config, &window.category)?;
+    }
+    Ok(())
+}
+
+/**
+ * Returns the strategy of a publication starting at `time`, and whether it uses autobuy
+ *
+ * The category in force when the publication starts is used for the whole publication.
+ * Without daily profile, `strat` is used.
+ */
+fn profile_strat(config: &Config, profile: Option<&DailyProfile>, strat: &str, time: f64) -> Result<(String, bool), SimError> {
+    match profile.map(|profile| profile.category_at(time)) {
+        None => Ok((strat.to_owned(), false)),
+        Some(Some(category)) => Ok((strats::category_name(config, category)?.to_owned(), false)),
+        Some(None) => Ok((strats::category_name(config, "Idle")?.to_owned(), true))
+    }
+}
+
+fn chain_sim(query: ChainSimQuery) -> Result<ChainSimResponse, SimError> {
+    let custom_strat = compile_custom_strat(query.custom_strat.as_ref())?;
+    compiled_chain_sim(query, custom_strat)
+}
+
+/** Runs a chain sim with its compiled custom strategy, the custom strategy of the query is ignored */
+fn compiled_chain_sim(query: ChainSimQuery, custom_strat: Option<Arc<CustomStrat>>) -> Result<ChainSimResponse, SimError> {
+    if query.cap.is_none() && query.tau_target.is_none() && query.time_budget.is_none() {
+        return Err(SimError::invalid("A chain sim needs a cap, a tau target or a time budget"));
+    }
+    let config = current_config()?;
+    let tau_factor = config
+        .theories.get(&query.theory)
+        .ok_or_else(|| SimError::no_theory_config(query.theory))?
+        .tau_factor;
+    if let Some(profile) = &query.daily_profile {
+        validate_profile(&config, profile)?;
+    }
+
+    // A tau target is reached at a given rho, which is always used as a hard cap
+    let goal_rho = query.tau_target.map(|target| query.rho * target.powf64(1. / tau_factor));
+    let cap = query.cap.unwrap_or(LogNum::infinity()).min(goal_rho.unwrap_or(LogNum::infinity()));
+    let sim_cap = goal_rho.unwrap_or(LogNum::infinity())
+        .min(if query.hard_cap { cap } else { LogNum::infinity() });
+
     let mut rho = query.rho;
     let mut time = 0f64;
     let mut last_strat = String::new();
 
     let mut results: Vec<SimResult> = Vec::new();
 
-    while rho < query.cap {
-        let res = single_sim(SingleSimQuery {
+    while rho < cap {
+        if results.len() >= MAX_PUBLICATIONS {
+            return Err(SimError::limit(format!("A chain sim cannot simulate more than {MAX_PUBLICATIONS} publications")));
+        }
+        let time_cap = query.time_budget.map(|budget| budget - time);
+        if time_cap.is_some_and(|time_cap| time_cap <= 0.) {
+            break;
+        }
+        let (strat, autobuy) = profile_strat(&config, query.daily_profile.as_ref(), &query.strat, time)?;
+        let res = compiled_single_sim(SingleSimQuery {
             theory: query.theory,
-            strat: query.strat.clone(),
+            strat,
             rho,
             sigma: query.sigma,
             settings: query.settings.clone(),
-            cap: if query.hard_cap { Some(query.cap) } else { None },
-            last_strat: Some(last_strat.clone())
-        })?.result;
-
-        // Test result to test functionality
-        let res = SimResult {
-            theory: query.theory,
-            sigma: query.sigma,
-            last_pub: rho,
-            pub_rho: rho * LogNum::from(10.),
-            delta_tau: LogNum::from(10.),
-            pub_multi: 2.718,
-            strat: "TEST".to_owned(),
-            tau_h: 3.1415,
-            time: 3600.,
-            bought_vars: Vec::new()
-        };
+            cap: if sim_cap < LogNum::infinity() { Some(sim_cap) } else { None },
+            last_strat: Some(last_strat.clone()),
+            time_cap,
+            check_ins: query.check_ins.as_ref().map(|check_ins| check_ins.shifted(time)),
+            autobuy,
+            custom_strat: None
+        }, custom_strat.clone())?.result;
 
         rho = res.pub_rho;
         time += res.time;
@@ -54,34 +199,50 @@ fn chain_sim(query: ChainSimQuery) -> Result<ChainSimResponse, String> {
         results.push(res);
     }
 
-    let delta_tau = (rho / query.rho).powf64(
-        CONFIG.get().unwrap().theories.get(&query.theory).unwrap().tau_factor
-    );
+    let delta_tau = (rho / query.rho).powf64(tau_factor);
+    let average_rate = delta_tau.log10_f64() / (time / 3600.);
+    // The missing tau of an unreached target is gained at the average rate of the chain
+    let eta = query.tau_target.map(|target| {
+        let missing_tau = (target.log10_f64() - delta_tau.log10_f64()).max(0.);
+        if missing_tau > 0. { time + missing_tau / average_rate * 3600. } else { time }
+    });
 
     Ok(ChainSimResponse {
         results,
         delta_tau,
-        average_rate: delta_tau.log10_f64() / (time / 3600.),
-        total_time: time
+        average_rate,
+        total_time: time,
+        eta: eta.filter(|eta| eta.is_finite())
     })
 }
 
-fn step_sim(query: StepSimQuery) -> Result<StepSimResponse, String> {
+fn step_sim(query: StepSimQuery) -> Result<StepSimResponse, SimError> {
+    if query.step <= LogNum::from(1.) {
+        return Err(SimError::invalid("The rho step must be greater than 1"));
+    }
+    let custom_strat = compile_custom_strat(query.custom_strat.as_ref())?;
     let mut rho = query.rho;
     let mut last_strat = String::new();
 
     let mut results: Vec<SimResult> = Vec::new();
 
     while rho < query.cap * LogNum::from(1.001) {
-        let res = single_sim(SingleSimQuery {
+        if results.len() >= MAX_PUBLICATIONS {
+            return Err(SimError::limit(format!("A step sim cannot simulate more than {MAX_PUBLICATIONS} publications")));
+        }
+        let res = compiled_single_sim(SingleSimQuery {
             theory: query.theory,
             strat: query.strat.clone(),
             rho,
             sigma: query.sigma,
             settings: query.settings.clone(),
             cap: None,
-            last_strat: Some(last_strat.clone())
-        })?.result;
+            last_strat: Some(last_strat.clone()),
+            time_cap: None,
+            check_ins: None,
+            autobuy: false,
+            custom_strat: None
+        }, custom_strat.clone())?.result;
 
         rho *= query.step;
         last_strat.clear();
@@ -92,7 +253,108 @@ fn step_sim(query: StepSimQuery) -> Result<StepSimResponse, String> {
     Ok(StepSimResponse { results })
 }
 
-fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
+/**
+ * Runs a chain sim from every step of the rho range to the cap
+ *
+ * Each result covers the publications of the chain after which the tau/h since the start is the
+ * highest, its strategy is the number of these publications.
+ */
+fn step_chain_sim(query: StepChainQuery) -> Result<StepSimResponse, SimError> {
+    if query.step <= LogNum::from(1.) {
+        return Err(SimError::invalid("The rho step must be greater than 1"));
+    }
+    let custom_strat = compile_custom_strat(query.custom_strat.as_ref())?;
+    let mut rho = query.rho;
+
+    let mut results: Vec<SimResult> = Vec::new();
+
+    while rho * query.step < query.cap * LogNum::from(1.001) {
+        if results.len() >= MAX_PUBLICATIONS {
+            return Err(SimError::limit(format!("A step chain sim cannot simulate more than {MAX_PUBLICATIONS} chains")));
+        }
+        let chain = compiled_chain_sim(ChainSimQuery {
+            theory: query.theory,
+            strat: query.strat.clone(),
+            sigma: query.sigma,
+            rho,
+            cap: Some(query.cap),
+            hard_cap: query.hard_cap,
+            tau_target: None,
+            time_budget: None,
+            check_ins: None,
+            daily_profile: None,
+            custom_strat: None,
+            settings: query.settings.clone()
+        }, custom_strat.clone())?;
+
+        let mut delta_tau = lognum::ONE;
+        let mut time = 0.;
+        let mut ticks = 0;
+        let mut best_res: Option<SimResult> = None;
+        for (i, res) in chain.results.iter().enumerate() {
+            delta_tau *= res.delta_tau;
+            time += res.time;
+            ticks += res.ticks;
+            let tau_h = delta_tau.log10_f64() / (time / 3600.);
+            if best_res.as_ref().is_some_and(|best| best.tau_h >= tau_h) {
+                continue;
+            }
+            best_res = Some(SimResult {
+                theory: query.theory,
+                sigma: query.sigma,
+                last_pub: rho,
+                pub_rho: res.pub_rho,
+                delta_tau,
+                pub_multi: 1.,
+                strat: format!("{} pub{}", i + 1, if i > 0 { "s" } else { "" }),
+                tau_h,
+                time,
+                ticks,
+                bought_vars: Vec::new()
+            });
+        }
+        results.extend(best_res);
+        rho *= query.step;
+    }
+
+    Ok(StepSimResponse { results })
+}
+
+/** Returns true if `theory` is a custom theory whose last publication reached the maximum tau (e600) */
+fn is_completed_ct(theory: TheoryType, rho: LogNum, tau_factor: f64) -> bool {
+    theory as usize > TheoryType::T8 as usize && rho.log10_f64() * tau_factor >= 600.
+}
+
+/** Converts a last publication between rho, tau and total multiplier */
+fn convert(query: ConvertQuery) -> Result<ConvertResponse, SimError> {
+    let config = current_config()?;
+    let tau_factor = config
+        .theories.get(&query.theory)
+        .ok_or_else(|| SimError::no_theory_config(query.theory))?
+        .tau_factor;
+    let model = MultiplierModel::of(query.theory);
+
+    let rho = match query.unit {
+        ValueUnit::Rho => query.value.log10_f64(),
+        ValueUnit::Tau => query.value.log10_f64() / tau_factor,
+        ValueUnit::Multiplier => model.rho(query.value.log10_f64(), tau_factor, query.sigma)
+    };
+    Ok(ConvertResponse {
+        theory: query.theory,
+        rho: LogNum::from_log10(rho),
+        tau: LogNum::from_log10(rho * tau_factor),
+        multiplier: LogNum::from_log10(model.multiplier(rho, tau_factor, query.sigma))
+    })
+}
+
+/**
+ * Runs the best active and idle strategies of every theory of a student distribution
+ *
+ * Theories whose sim is not ported yet are skipped. `show_a23` only changes the strategy names of
+ * EF, which is not ported, so it is not read yet.
+ */
+fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, SimError> {
+    let config = current_config()?;
     let mut results: Vec<SimAllResult> = Vec::new();
 
     for (i, rho) in query.values.iter().enumerate() {
@@ -100,6 +362,14 @@ fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
             if let Ok(theory) = TheoryType::try_from(i) { theory } 
             else { continue; };
         if *rho <= lognum::ONE { continue; };
+        if !is_supported(theory) { continue; };
+        let theory_config = config
+            .theories.get(&theory)
+            .ok_or_else(|| SimError::no_theory_config(theory))?;
+        let hidden = is_unofficial(theory) || theory_config.ui_visible == Some(false);
+        if hidden && !query.settings.show_unofficials { continue; };
+        let completed = is_completed_ct(theory, *rho, theory_config.tau_factor);
+        if completed && matches!(query.settings.completed_cts, CompletedCTs::No) { continue; };
 
         let active_res = if query.settings.sim_all_strats != SimAllStrats::Idle {
             single_sim(SingleSimQuery {
@@ -110,6 +380,10 @@ fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
                 rho: *rho,
                 cap: None,
                 last_strat: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                custom_strat: None,
                 settings: query.settings.clone()
             })?.result
         } else { SimResult::default() };
@@ -123,12 +397,18 @@ fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
                 rho: *rho,
                 cap: None,
                 last_strat: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                custom_strat: None,
                 settings: query.settings.clone()
             })?.result
         } else { SimResult::default() };
 
         results.push(SimAllResult {
             theory,
+            multiplier: LogNum::from_log10(MultiplierModel::of(theory).multiplier(rho.log10_f64(), theory_config.tau_factor, query.sigma)),
+            completed,
             ratio: if query.settings.sim_all_strats == SimAllStrats::All {
                 active_res.tau_h / idle_res.tau_h
             } else { 1. },
@@ -138,6 +418,11 @@ fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
         })
     }
 
+    if matches!(query.settings.completed_cts, CompletedCTs::End) {
+        // The sort is stable, the other theories keep their order
+        results.sort_by_key(|res| res.completed);
+    }
+
     Ok(SimAllResponse {
         sigma: query.sigma,
         strat_type: query.settings.sim_all_strats,
@@ -146,11 +431,312 @@ fn sim_all(query: SimAllQuery) -> Result<SimAllResponse, String> {
     })
 }
 
-pub fn simulate(query: SimQuery) -> Result<SimResponse, String> {
+/**
+ * Parses and runs a query
+ *
+ * A query can carry the `api_version` it is written for, and a `config` object next to its data,
+ * merged into the config for this query only.
+ */
+pub fn run(mut query: serde_json::Value) -> Result<SimResponse, SimError> {
+    let api_version = query.as_object_mut().and_then(|query| query.remove("api_version"));
+    match api_version {
+        None => {},
+        Some(version) if version.as_u64() == Some(API_VERSION as u64) => {},
+        Some(version) => return Err(match version.as_u64().and_then(|version| u32::try_from(version).ok()) {
+            Some(requested) => SimError::UnsupportedVersion { requested, supported: API_VERSION },
+            None => SimError::parse(format!("Invalid API version {version}"))
+        })
+    }
+    let overrides = query.as_object_mut().and_then(|query| query.remove("config"));
+    with_config_overrides(overrides.as_ref(), || parse_query(query).and_then(simulate))
+}
+
+pub fn simulate(query: SimQuery) -> Result<SimResponse, SimError> {
     match query {
         SimQuery::Single(q) => Ok(SimResponse::Single(single_sim(q)?)),
+        SimQuery::Resume(q) => Ok(SimResponse::Resume(resume_sim(q)?)),
         SimQuery::Chain(q) => Ok(SimResponse::Chain(chain_sim(q)?)),
         SimQuery::Step(q) => Ok(SimResponse::Step(step_sim(q)?)),
-        SimQuery::All(q) => Ok(SimResponse::All(sim_all(q)?))
+        SimQuery::StepChain(q) => Ok(SimResponse::Step(step_chain_sim(q)?)),
+        SimQuery::All(q) => Ok(SimResponse::All(sim_all(q)?)),
+        SimQuery::Plan(q) => Ok(SimResponse::Plan(planner::plan(q)?)),
+        SimQuery::Convergence(q) => Ok(SimResponse::Convergence(convergence::convergence(q)?)),
+        SimQuery::Zeros(q) => Ok(SimResponse::Zeros(rz_zeros(q)?)),
+        SimQuery::Tune(q) => Ok(SimResponse::Tune(tune::tune(q)?)),
+        SimQuery::Crossover(q) => Ok(SimResponse::Crossover(crossover::find_crossovers(q)?)),
+        SimQuery::Compare(q) => Ok(SimResponse::Compare(compare::compare(q)?)),
+        SimQuery::FilterSweep(q) => Ok(SimResponse::FilterSweep(filter_sweep::filter_sweep(q)?)),
+        SimQuery::PubTable(q) => Ok(SimResponse::PubTable(generate_pub_table(q)?)),
+        SimQuery::Describe(q) => Ok(SimResponse::Describe(describe::describe(q)?)),
+        SimQuery::Convert(q) => Ok(SimResponse::Convert(convert(q)?)),
+        SimQuery::Batch(q) => Ok(SimResponse::Batch(batch::batch(q)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+    use crate::theories::test_data;
+    use crate::utils::schedule::{ActivityWindow, CheckInSchedule, DailyProfile};
+
+    fn chain_query(cap: Option<f64>, tau_target: Option<f64>, time_budget: Option<f64>) -> ChainSimQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = true;
+        ChainSimQuery {
+            theory: TheoryType::BT,
+            strat: "BTd".to_owned(),
+            sigma: 0,
+            rho: LogNum::from_log10(100.),
+            cap: cap.map(LogNum::from_log10),
+            hard_cap: false,
+            tau_target: tau_target.map(LogNum::from_log10),
+            time_budget,
+            check_ins: None,
+            daily_profile: None,
+            custom_strat: None,
+            settings
+        }
+    }
+
+    #[test]
+    fn chain_tau_target() {
+        let res = chain_sim(chain_query(None, Some(50.), None)).unwrap();
+        assert!((res.delta_tau.log10_f64() - 50.).abs() < 0.1);
+        assert_eq!(res.eta, Some(res.total_time));
+    }
+
+    #[test]
+    fn chain_time_budget() {
+        let budget = 12. * 3600.;
+        let res = chain_sim(chain_query(Some(1000.), None, Some(budget))).unwrap();
+        assert!(res.total_time <= budget + 60.);
+        assert!(res.total_time >= budget - 60.);
+        assert!(res.results.last().unwrap().pub_rho.log10_f64() < 1000.);
+        assert_eq!(res.eta, None);
+
+        // The time to an unreached target is extrapolated
+        let res = chain_sim(chain_query(None, Some(1000.), Some(budget))).unwrap();
+        let eta = res.eta.unwrap();
+        assert!((eta - res.total_time - (1000. - res.delta_tau.log10_f64()) / res.average_rate * 3600.).abs() < 1e-6 * eta);
+        assert!(eta > budget);
+    }
+
+    #[test]
+    fn chain_goals() {
+        let capped = chain_sim(chain_query(Some(150.), None, None)).unwrap();
+        assert!(capped.results.last().unwrap().pub_rho.log10_f64() >= 150.);
+        assert!(chain_sim(chain_query(None, None, None)).is_err());
+    }
+
+    #[test]
+    fn api_versions() {
+        let query = |api_version: serde_json::Value| serde_json::json!({
+            "api_version": api_version,
+            "type": "describe",
+            "data": { "theory": "BT" }
+        });
+        assert!(matches!(run(query(API_VERSION.into())), Ok(SimResponse::Describe(_))));
+        assert_eq!(run(query(2.into())).unwrap_err(), SimError::UnsupportedVersion { requested: 2, supported: API_VERSION });
+        assert!(matches!(run(query("1".into())), Err(SimError::Parse { .. })));
+    }
+
+    #[test]
+    fn step_chains() {
+        let query = chain_query(None, None, None);
+        let res = step_chain_sim(StepChainQuery {
+            theory: query.theory,
+            strat: query.strat,
+            sigma: 0,
+            rho: query.rho,
+            cap: LogNum::from_log10(130.),
+            step: LogNum::from_log10(10.),
+            hard_cap: false,
+            custom_strat: None,
+            settings: query.settings
+        }).unwrap();
+        assert_eq!(res.results.len(), 3);
+        for (i, res) in res.results.iter().enumerate() {
+            assert!((res.last_pub.log10_f64() - 100. - 10. * i as f64).abs() < 1e-9);
+            assert!(res.pub_rho > res.last_pub && res.tau_h > 0.);
+        }
+    }
+
+    #[test]
+    fn step_errors() {
+        let query = chain_query(None, None, None);
+        let step_query = |step: f64, strat: &str| StepSimQuery {
+            theory: query.theory,
+            strat: strat.to_owned(),
+            sigma: 0,
+            rho: query.rho,
+            cap: LogNum::from_log10(110.),
+            step: LogNum::from_f64(step),
+            custom_strat: None,
+            settings: query.settings.clone()
+        };
+        assert!(matches!(step_sim(step_query(1., "BTd")), Err(SimError::InvalidQuery { .. })));
+        assert_eq!(step_sim(step_query(10., "BTx")).unwrap_err(), SimError::unknown_strategy(TheoryType::BT, "BTx"));
+        let mut query = chain_query(Some(150.), None, None);
+        query.settings = SimSettings { show_unofficials: false, ..query.settings };
+        assert!(matches!(chain_sim(query), Err(SimError::InvalidQuery { .. })));
+    }
+
+    #[test]
+    fn chain_check_ins() {
+        let period = 8. * 3600.;
+        let mut query = chain_query(Some(150.), None, None);
+        query.check_ins = Some(CheckInSchedule {
+            times: vec![0.],
+            period,
+            offset: 0.,
+            buy_at_check_ins: false
+        });
+        let res = chain_sim(query).unwrap();
+        let mut time = 0.;
+        for res in &res.results {
+            // Publications happen on the first tick after a check-in
+            time += res.time;
+            let since_check_in = time - (time / period).round() * period;
+            assert!(since_check_in.abs() < 60., "{time} is not a check-in");
+        }
+        assert!(res.results.len() > 1);
+    }
+
+    fn resume_query(state: ResumeState) -> ResumeQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = true;
+        ResumeQuery {
+            theory: TheoryType::BT,
+            strat: "BTd".to_owned(),
+            sigma: 0,
+            last_pub: LogNum::from_log10(100.),
+            last_strat: None,
+            state,
+            settings
+        }
+    }
+
+    fn resume_state(rho: f64, t: f64) -> ResumeState {
+        ResumeState {
+            rho: LogNum::from_log10(rho),
+            max_rho: None,
+            currencies: IndexMap::new(),
+            levels: IndexMap::new(),
+            t,
+            milestones: None
+        }
+    }
+
+    #[test]
+    fn resume_from_start() {
+        let query = chain_query(None, None, None);
+        let fresh = single_sim(SingleSimQuery {
+            theory: query.theory,
+            strat: query.strat,
+            sigma: 0,
+            rho: query.rho,
+            cap: None,
+            last_strat: None,
+            time_cap: None,
+            check_ins: None,
+            autobuy: false,
+            custom_strat: None,
+            settings: query.settings
+        }).unwrap().result;
+        let resumed = resume_sim(resume_query(resume_state(0., 0.))).unwrap();
+        assert_eq!(resumed.result.tau_h, fresh.tau_h);
+        assert_eq!(resumed.remaining_time, fresh.time);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn resume_late_pub() {
+        let mut state = resume_state(105., 24. * 3600.);
+        state.levels.insert("tai".to_owned(), 100);
+        state.levels.insert("rao".to_owned(), 100);
+        let res = resume_sim(resume_query(state)).unwrap();
+        assert!(res.remaining_time < 60.);
+        assert!(res.result.pub_rho.log10_f64() >= 105.);
+
+        let mut state = resume_state(105., 3600.);
+        state.levels.insert("c1".to_owned(), 10);
+        assert!(resume_sim(resume_query(state)).is_err());
+        let mut state = resume_state(105., 3600.);
+        state.currencies.insert("q1".to_owned(), LogNum::from(1.));
+        assert!(resume_sim(resume_query(state)).is_err());
+    }
+
+    fn sim_all_query(values: &[(TheoryType, f64)], completed_cts: CompletedCTs, show_unofficials: bool) -> SimAllQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = show_unofficials;
+        settings.completed_cts = completed_cts;
+        settings.sim_all_strats = SimAllStrats::Idle;
+        let mut rhos = vec![lognum::ZERO; TheoryType::TC as usize + 1];
+        for (theory, rho) in values {
+            rhos[*theory as usize] = LogNum::from_log10(*rho);
+        }
+        SimAllQuery {
+            values: rhos,
+            sigma: 0,
+            very_active: false,
+            semi_idle: false,
+            settings
+        }
+    }
+
+    #[test]
+    fn sim_all_completed_cts() {
+        // T1 is not ported and is skipped
+        let values = [(TheoryType::T1, 100.), (TheoryType::MF, 700.), (TheoryType::BaP, 100.), (TheoryType::BT, 100.)];
+        let theories = |completed_cts: CompletedCTs, show_unofficials: bool| -> Vec<TheoryType> {
+            sim_all(sim_all_query(&values, completed_cts, show_unofficials)).unwrap()
+                .results.iter().map(|res| res.theory).collect()
+        };
+        assert_eq!(theories(CompletedCTs::In, true), [TheoryType::MF, TheoryType::BaP, TheoryType::BT]);
+        assert_eq!(theories(CompletedCTs::End, true), [TheoryType::BaP, TheoryType::BT, TheoryType::MF]);
+        assert_eq!(theories(CompletedCTs::No, true), [TheoryType::BaP, TheoryType::BT]);
+        assert_eq!(theories(CompletedCTs::In, false), [TheoryType::MF, TheoryType::BaP]);
+    }
+
+    #[test]
+    fn convert_units() {
+        let convert_to = |value: f64, unit: ValueUnit| convert(ConvertQuery {
+            theory: TheoryType::BaP,
+            sigma: 0,
+            value: LogNum::from_log10(value),
+            unit
+        }).unwrap();
+        let res = convert_to(100., ValueUnit::Rho);
+        assert!((res.tau.log10_f64() - 40.).abs() < 1e-9);
+        assert!((res.multiplier.log10_f64() - (40. * 0.132075 + 5f64.log10())).abs() < 1e-9);
+        for (value, unit) in [(40., ValueUnit::Tau), (res.multiplier.log10_f64(), ValueUnit::Multiplier)] {
+            assert!((convert_to(value, unit).rho.log10_f64() - 100.).abs() < 1e-9);
+        }
+        assert_eq!(convert_to(5., ValueUnit::Rho).multiplier.log10_f64(), 0.);
+    }
+
+    #[test]
+    fn chain_daily_profile() {
+        let mut query = chain_query(Some(130.), None, None);
+        query.strat = "Best Overall".to_owned();
+        query.daily_profile = Some(DailyProfile {
+            windows: vec![ActivityWindow { start: 0., end: 43200., category: "Active".to_owned() }],
+            offset: 36000.
+        });
+        let res = chain_sim(query).unwrap();
+        let mut time = 0.;
+        for res in &res.results {
+            let offline = (time + 36000.) % 86400. >= 43200.;
+            assert_eq!(res.strat.ends_with(" autobuy"), offline, "{} at {time}", res.strat);
+            time += res.time;
+        }
+        assert!(res.results.iter().any(|res| res.strat.ends_with(" autobuy")));
+
+        let mut query = chain_query(Some(130.), None, None);
+        query.daily_profile = Some(DailyProfile {
+            windows: vec![ActivityWindow { start: 0., end: 43200., category: "Lazy".to_owned() }],
+            offset: 0.
+        });
+        assert!(chain_sim(query).is_err());
+    }
+}
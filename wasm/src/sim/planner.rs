@@ -0,0 +1,189 @@
+/*!
+ * Rotation planner between theories
+ *
+ * The plan is built greedily: before each publication, every theory is simulated from its current
+ * rho and the one with the best tau/h is published. Consecutive publications of the same theory
+ * are grouped into a single step. Theories whose sim is not ported yet keep their rho.
+ */
+
+use crate::api::{
+    config::{TheoryType, current_config},
+    error::SimError,
+    query::{PlanQuery, SingleSimQuery},
+    response::{PlanResponse, PlanStep}
+};
+use crate::theories::{is_supported, is_unofficial};
+use crate::utils::{
+    lognum::{self, LogNum},
+    result::SimResult
+};
+
+use super::{profile_strat, single_sim, validate_profile};
+
+/** Theory of the plan with its current state */
+struct PlannedTheory {
+    theory: TheoryType,
+    /** Index of the theory in the query values */
+    index: usize,
+    rho: LogNum,
+    last_strat: String,
+    /** Last sim of the theory with its strategy, kept while the rho of the theory is unchanged */
+    cached: Option<(String, bool, SimResult)>
+}
+
+impl PlannedTheory {
+    /** Sims the next publication, `time_cap` is the time left in the horizon */
+    fn next_pub(&mut self, query: &PlanQuery, strat: &str, autobuy: bool, time_cap: f64) -> Result<SimResult, SimError> {
+        // Results that were not cut by the horizon stay valid as long as the theory is not published
+        if let Some((_, _, res)) = self.cached.as_ref()
+            .filter(|(cached_strat, cached_autobuy, res)| cached_strat == strat && *cached_autobuy == autobuy && res.time < time_cap) {
+            return Ok(res.clone());
+        }
+        let res = single_sim(SingleSimQuery {
+            theory: self.theory,
+            strat: strat.to_owned(),
+            sigma: query.sigma,
+            rho: self.rho,
+            cap: None,
+            last_strat: Some(self.last_strat.clone()),
+            time_cap: Some(time_cap),
+            check_ins: None,
+            autobuy,
+            custom_strat: None,
+            settings: query.settings.clone()
+        })?.result;
+        self.cached = Some((strat.to_owned(), autobuy, res.clone()));
+        Ok(res)
+    }
+}
+
+/** Plans the theories to publish over the horizon of `query` */
+pub fn plan(query: PlanQuery) -> Result<PlanResponse, SimError> {
+    let config = current_config()?;
+    if !(query.horizon.is_finite() && query.horizon > 0.) {
+        return Err(SimError::invalid("The plan horizon must be positive"));
+    }
+    if let Some(profile) = &query.daily_profile {
+        validate_profile(&config, profile)?;
+    }
+
+    let mut theories: Vec<PlannedTheory> = query.values.iter().enumerate()
+        .filter_map(|(index, rho)| {
+            let theory = TheoryType::try_from(index).ok()?;
+            if *rho <= lognum::ONE || !is_supported(theory) || (is_unofficial(theory) && !query.settings.show_unofficials) {
+                return None;
+            }
+            Some(PlannedTheory { theory, index, rho: *rho, last_strat: String::new(), cached: None })
+        })
+        .collect();
+
+    let mut steps: Vec<PlanStep> = Vec::new();
+    let mut time = 0.;
+    let mut total_tau = 0.;
+    while time < query.horizon && !theories.is_empty() {
+        let (strat, autobuy) = profile_strat(&config, query.daily_profile.as_ref(), &query.strat, time)?;
+
+        let mut best: Option<(usize, SimResult)> = None;
+        for (i, theory) in theories.iter_mut().enumerate() {
+            let res = theory.next_pub(&query, &strat, autobuy, query.horizon - time)?;
+            if best.as_ref().is_none_or(|(_, best_res)| res.tau_h > best_res.tau_h) {
+                best = Some((i, res));
+            }
+        }
+        let Some((i, res)) = best.filter(|(_, res)| res.tau_h > 0. && res.time > 0.) else { break; };
+
+        let theory = &mut theories[i];
+        theory.rho = res.pub_rho;
+        theory.last_strat.clear();
+        theory.last_strat.push_str(res.strat.split_whitespace().next().unwrap_or(""));
+        theory.cached = None;
+
+        let tau_gain = res.delta_tau.log10_f64();
+        time += res.time;
+        total_tau += tau_gain;
+        match steps.last_mut() {
+            Some(step) if step.theory == theory.theory && step.strat == strat => {
+                step.pubs += 1;
+                step.time += res.time;
+                step.tau_gain += tau_gain;
+                step.results.push(res);
+            },
+            _ => steps.push(PlanStep {
+                theory: theory.theory,
+                strat,
+                pubs: 1,
+                start_time: time - res.time,
+                time: res.time,
+                tau_gain,
+                results: vec![res],
+                cumulative_tau: 0.,
+                cumulative_time: 0.
+            })
+        }
+        if let Some(step) = steps.last_mut() {
+            step.cumulative_tau = total_tau;
+            step.cumulative_time = time;
+        }
+    }
+
+    let mut values = query.values.clone();
+    for theory in &theories {
+        values[theory.index] = theory.rho;
+    }
+
+    Ok(PlanResponse {
+        steps,
+        values,
+        total_tau,
+        total_time: time
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::test_data;
+
+    fn query(horizon: f64) -> PlanQuery {
+        let mut settings = test_data(TheoryType::BT, "BTd", 0.).settings;
+        settings.show_unofficials = true;
+        let mut values = vec![lognum::ZERO; TheoryType::TC as usize + 1];
+        values[TheoryType::T1 as usize] = LogNum::from_log10(100.);
+        values[TheoryType::MF as usize] = LogNum::from_log10(100.);
+        values[TheoryType::BT as usize] = LogNum::from_log10(100.);
+        PlanQuery {
+            values,
+            sigma: 0,
+            horizon,
+            strat: "Best Overall".to_owned(),
+            daily_profile: None,
+            settings
+        }
+    }
+
+    #[test]
+    fn plans_rotation() {
+        let horizon = 2. * 86400.;
+        let res = plan(query(horizon)).unwrap();
+        assert!(res.total_time <= horizon + 60.);
+        assert!(res.steps.len() > 1);
+        let mut time = 0.;
+        let mut tau = 0.;
+        for (i, step) in res.steps.iter().enumerate() {
+            assert_eq!(step.pubs as usize, step.results.len());
+            assert!((step.start_time - time).abs() < 1e-6);
+            time += step.time;
+            tau += step.tau_gain;
+            assert!((step.cumulative_time - time).abs() < 1e-6);
+            assert!((step.cumulative_tau - tau).abs() < 1e-6);
+            if i > 0 {
+                assert_ne!(step.theory, res.steps[i - 1].theory);
+            }
+        }
+        assert!(res.values[TheoryType::BT as usize] > LogNum::from_log10(100.));
+        // T1 is not ported and keeps its rho
+        assert_eq!(res.values[TheoryType::T1 as usize], LogNum::from_log10(100.));
+        assert!(res.steps.iter().all(|step| step.theory != TheoryType::T1));
+        assert!(plan(query(0.)).is_err());
+    }
+}
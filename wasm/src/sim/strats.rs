@@ -0,0 +1,106 @@
+/*!
+ * Expansion of strategy categories (Best Overall, Best Active...) into theory strategies
+ */
+
+use crate::api::{
+    config::{Config, TheoryType},
+    error::SimError
+};
+use crate::utils::{
+    expr::{self, ExprContext, ExprValue},
+    lognum::LogNum
+};
+
+/** Names under which the strategy categories are available in conditions, in the order of `strat_categories` */
+const CATEGORY_IDENTS: [&str; 4] = ["very_active", "active", "semi_idle", "idle"];
+
+/** Names under which the strategy categories are written in conditions, in the order of `strat_categories` */
+pub const CATEGORY_NAMES: [&str; 4] = ["Very-Active", "Active", "Semi-Idle", "Idle"];
+
+/** Context of the data.json strategy conditions */
+pub struct StratConditionContext<'a> {
+    categories: [bool; 4],
+    rho: f64,
+    last_strat: &'a str
+}
+
+impl<'a> StratConditionContext<'a> {
+    /** Creates the context of the strategy category `category` */
+    pub fn new(config: &Config, rho: LogNum, category: &str, last_strat: &'a str) -> Self {
+        let mut categories = [false; 4];
+        for (i, name) in config.strat_categories.iter().take(4).enumerate() {
+            categories[i] = name == category;
+        }
+        StratConditionContext {
+            categories,
+            rho: rho.log10_f64(),
+            last_strat
+        }
+    }
+}
+
+impl ExprContext for StratConditionContext<'_> {
+    fn get(&self, name: &str) -> Option<ExprValue> {
+        if let Some(i) = CATEGORY_IDENTS.iter().position(|ident| *ident == name) {
+            return Some(ExprValue::Bool(self.categories[i]));
+        }
+        match name {
+            "rho" => Some(ExprValue::Number(self.rho)),
+            "laststrat" => Some(ExprValue::Str(self.last_strat.to_owned())),
+            _ => None
+        }
+    }
+}
+
+/** Evaluates a data.json strategy condition, a condition that cannot be evaluated is a config error */
+pub fn eval_condition(condition: &str, ctx: &StratConditionContext) -> Result<bool, SimError> {
+    expr::parse(condition)
+        .and_then(|expr| expr.eval_bool(ctx))
+        .map_err(SimError::config)
+}
+
+/**
+ * Returns the `strat_categories` entry of a category
+ *
+ * Categories can be given by their `strat_categories` name (Best Active) or their condition name (Active).
+ */
+pub fn category_name<'a>(config: &'a Config, category: &str) -> Result<&'a str, SimError> {
+    config.strat_categories.iter()
+        .take(4)
+        .zip(CATEGORY_NAMES)
+        .find(|(name, short_name)| *name == category || *short_name == category)
+        .map(|(name, _)| name.as_str())
+        .ok_or_else(|| SimError::invalid(format!("Unknown strategy category {category}")))
+}
+
+/**
+ * Returns the strategies of `theory` matching the strategy category `category`
+ *
+ * A strategy is kept if both its filter condition and its forced condition are satisfied.
+ */
+pub fn get_strats(
+    config: &Config,
+    theory: TheoryType,
+    rho: LogNum,
+    category: &str,
+    last_strat: &str
+) -> Result<Vec<String>, SimError> {
+    let theory_config = config.theories.get(&theory)
+        .ok_or_else(|| SimError::no_theory_config(theory))?;
+
+    let ctx = StratConditionContext::new(config, rho, category, last_strat);
+
+    let mut strats = Vec::new();
+    for (name, strat) in &theory_config.strats {
+        let filter = eval_condition(&strat.strat_filter_condition, &ctx)?;
+        let forced = match &strat.forced_condition {
+            Some(cond) => eval_condition(cond, &ctx)?,
+            None => true
+        };
+        if filter && forced {
+            strats.push(name.clone());
+        }
+    }
+
+    Ok(strats)
+}
@@ -0,0 +1,351 @@
+/*!
+ * Tuning of the parameters of a strategy
+ *
+ * The parameters are searched with the Nelder-Mead method, maximizing the average tau/h of
+ * single sims over a range of rho. The search happens in a normalized space where every
+ * parameter range is mapped to [0, 1].
+ *
+ * Either the parameters of a custom strategy or the weights of a built-in "d" strategy are
+ * tuned. Built-in weights are tuned through a custom strategy equivalent to the built-in one.
+ */
+
+use indexmap::IndexMap;
+
+use crate::api::{
+    config::TheoryType,
+    error::SimError,
+    query::{CustomStratQuery, ParamRange, SingleSimQuery, TuneQuery},
+    response::TuneResponse
+};
+use crate::utils::{lognum::LogNum, result::SimResult};
+
+use super::single_sim;
+
+/** Default maximum number of parameter sets evaluated */
+const DEFAULT_MAX_EVALS: u32 = 60;
+/** Maximum number of parameter sets a query can ask for */
+const MAX_EVALS: u32 = 1000;
+/** Size of the initial simplex in the normalized space */
+const INITIAL_STEP: f64 = 0.25;
+/** The search stops when the simplex is smaller than this in the normalized space */
+const MIN_SIMPLEX_SIZE: f64 = 1e-3;
+
+/**
+ * Built-in strategy `strat` written as a custom strategy whose parameters are its weights, with
+ * their default values
+ *
+ * The MF strategies are not listed, their conditions depend on state the custom strategies can't read.
+ */
+fn builtin_strat(theory: TheoryType, strat: &str) -> Option<CustomStratQuery> {
+    let custom_strat = |conditions: &[(&str, &str)], params: &[(&str, f64)]| CustomStratQuery {
+        name: Some(strat.to_owned()),
+        conditions: conditions.iter().map(|(var, condition)| (var.to_string(), condition.to_string())).collect(),
+        params: params.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    };
+
+    match (theory, strat) {
+        (TheoryType::BaP, "BaPd" | "BaPdMS") => Some(custom_strat(
+            &[("c1", "c1.cost + log10(weight * tdot.level % 64) < c2.cost && (ms[0] > 0 || c1.level < 65)")],
+            &[("weight", 0.5)]
+        )),
+        (TheoryType::BT, "BTd") => Some(custom_strat(
+            &[("tai", "tai.cost + log10((lastPub < 275 ? earlyOffset : offset) + tai.level % 10) < rao.cost")],
+            &[("earlyOffset", 12.), ("offset", 10.)]
+        )),
+        (TheoryType::TC, "TCd") => Some(custom_strat(&[("r1", "r1.cost + log10(ratio) < r2.cost")], &[("ratio", 10.)])),
+        _ => None
+    }
+}
+
+/** Best parameters evaluated so far */
+struct Best {
+    x: Vec<f64>,
+    score: f64,
+    results: Vec<SimResult>
+}
+
+/** Evaluates parameter sets within a budget and keeps the best one */
+struct Objective<'a> {
+    query: &'a TuneQuery,
+    custom_strat: CustomStratQuery,
+    rhos: Vec<LogNum>,
+    max_evals: u32,
+    evaluations: u32,
+    best: Best
+}
+
+impl<'a> Objective<'a> {
+    /** Evaluates the starting point, which counts toward the budget */
+    fn new(query: &'a TuneQuery, custom_strat: CustomStratQuery, rhos: Vec<LogNum>, max_evals: u32, start: Vec<f64>) -> Result<Self, SimError> {
+        let mut objective = Objective {
+            query,
+            custom_strat,
+            rhos,
+            max_evals,
+            evaluations: 1,
+            best: Best { x: start, score: 0., results: Vec::new() }
+        };
+        objective.best.results = objective.run(objective.params(&objective.best.x))?;
+        objective.best.score = average_tau_h(&objective.best.results);
+        Ok(objective)
+    }
+
+    fn params(&self, x: &[f64]) -> IndexMap<String, f64> {
+        self.query.params.iter()
+            .zip(x)
+            .map(|((name, range), x)| (name.clone(), range.min + x.clamp(0., 1.) * (range.max - range.min)))
+            .collect()
+    }
+
+    /** Runs the sims of the rho range with the given parameters */
+    fn run(&self, params: IndexMap<String, f64>) -> Result<Vec<SimResult>, SimError> {
+        let mut custom_strat = self.custom_strat.clone();
+        custom_strat.params.extend(params);
+
+        self.rhos.iter()
+            .map(|&rho| Ok(single_sim(SingleSimQuery {
+                theory: self.query.theory,
+                strat: self.query.strat.clone(),
+                sigma: self.query.sigma,
+                rho,
+                cap: None,
+                last_strat: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                custom_strat: Some(custom_strat.clone()),
+                settings: self.query.settings.clone()
+            })?.result))
+            .collect()
+    }
+
+    /**
+     * Average tau/h of the rho range for a point of the normalized space
+     *
+     * Returns None without running anything once the budget is spent.
+     */
+    fn eval(&mut self, x: &[f64]) -> Result<Option<f64>, SimError> {
+        if self.evaluations >= self.max_evals {
+            return Ok(None);
+        }
+        self.evaluations += 1;
+        let results = self.run(self.params(x))?;
+        let score = average_tau_h(&results);
+        if score > self.best.score {
+            self.best = Best { x: x.to_vec(), score, results };
+        }
+        Ok(Some(score))
+    }
+}
+
+fn average_tau_h(results: &[SimResult]) -> f64 {
+    results.iter().map(|res| res.tau_h).sum::<f64>() / results.len() as f64
+}
+
+fn normalize(value: f64, range: &ParamRange) -> f64 {
+    if range.max > range.min { (value - range.min) / (range.max - range.min) } else { 0. }
+}
+
+/**
+ * Maximizes `objective` with the Nelder-Mead method, starting from `start` whose score is `start_score`
+ *
+ * The best point is kept by the objective.
+ */
+fn nelder_mead(objective: &mut Objective, start: Vec<f64>, start_score: f64) -> Result<(), SimError> {
+    let n = start.len();
+    let clamp = |x: Vec<f64>| x.into_iter().map(|x| x.clamp(0., 1.)).collect::<Vec<f64>>();
+    // Points are sorted by decreasing score
+    let mut simplex = vec![(start.clone(), start_score)];
+    for i in 0..n {
+        let mut x = start.clone();
+        x[i] += if x[i] + INITIAL_STEP <= 1. { INITIAL_STEP } else { -INITIAL_STEP };
+        let Some(score) = objective.eval(&x)? else { return Ok(()) };
+        simplex.push((x, score));
+    }
+
+    loop {
+        simplex.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let size = simplex.iter()
+            .map(|(x, _)| x.iter().zip(&simplex[0].0).map(|(a, b)| (a - b).abs()).fold(0., f64::max))
+            .fold(0., f64::max);
+        if size < MIN_SIMPLEX_SIZE {
+            return Ok(());
+        }
+
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|(x, _)| x[i]).sum::<f64>() / n as f64)
+            .collect();
+        let towards = |coeff: f64, x: &[f64]| clamp(centroid.iter().zip(x).map(|(c, x)| c + coeff * (x - c)).collect());
+        let (worst, worst_score) = simplex[n].clone();
+
+        let reflected = towards(-1., &worst);
+        let Some(reflected_score) = objective.eval(&reflected)? else { return Ok(()) };
+        if reflected_score > simplex[0].1 {
+            let expanded = towards(-2., &worst);
+            let Some(expanded_score) = objective.eval(&expanded)? else { return Ok(()) };
+            simplex[n] = if expanded_score > reflected_score { (expanded, expanded_score) } else { (reflected, reflected_score) };
+        } else if reflected_score > simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_score);
+        } else {
+            let contracted = towards(0.5, &worst);
+            let Some(contracted_score) = objective.eval(&contracted)? else { return Ok(()) };
+            if contracted_score > worst_score {
+                simplex[n] = (contracted, contracted_score);
+            } else {
+                // Shrink towards the best point
+                let best = simplex[0].0.clone();
+                for point in simplex.iter_mut().skip(1) {
+                    let x: Vec<f64> = best.iter().zip(&point.0).map(|(b, x)| b + 0.5 * (x - b)).collect();
+                    let Some(score) = objective.eval(&x)? else { return Ok(()) };
+                    *point = (x, score);
+                }
+            }
+        }
+    }
+}
+
+/** Tunes the parameters of the custom strategy of `query`, or the weights of its built-in strategy */
+pub fn tune(query: TuneQuery) -> Result<TuneResponse, SimError> {
+    let custom_strat = match &query.custom_strat {
+        Some(custom_strat) => custom_strat.clone(),
+        None => builtin_strat(query.theory, &query.strat)
+            .ok_or_else(|| SimError::invalid(format!("Strategy {} has no built-in weights to tune", query.strat)))?
+    };
+    if query.params.is_empty() {
+        return Err(SimError::invalid("No parameters to tune"));
+    }
+    if let Some(name) = query.params.keys().find(|name| query.custom_strat.is_none() && !custom_strat.params.contains_key(*name)) {
+        return Err(SimError::invalid(format!("Strategy {} has no weight {name}", query.strat)));
+    }
+    if let Some((name, _)) = query.params.iter().find(|(_, range)| !range.min.is_finite() || !range.max.is_finite() || range.min > range.max) {
+        return Err(SimError::invalid(format!("Invalid range for parameter {name}")));
+    }
+    if query.step <= LogNum::from(1.) {
+        return Err(SimError::invalid("The rho step must be greater than 1"));
+    }
+
+    let mut rhos = Vec::new();
+    let mut rho = query.rho;
+    while rho < query.cap * LogNum::from(1.001) {
+        rhos.push(rho);
+        rho *= query.step;
+    }
+
+    let max_evals = query.max_evals.unwrap_or(DEFAULT_MAX_EVALS);
+    if max_evals > MAX_EVALS {
+        return Err(SimError::limit(format!("At most {MAX_EVALS} parameter sets can be evaluated")));
+    }
+    if max_evals == 0 {
+        return Err(SimError::invalid("At least one parameter set must be evaluated"));
+    }
+    let start: Vec<f64> = query.params.iter()
+        .map(|(name, range)| {
+            let default = range.default
+                .or_else(|| custom_strat.params.get(name).copied())
+                .unwrap_or((range.min + range.max) * 0.5);
+            normalize(default, range)
+        })
+        .collect();
+    let mut objective = Objective::new(&query, custom_strat, rhos, max_evals, start.clone())?;
+    let default_tau_h = objective.best.score;
+    nelder_mead(&mut objective, start, default_tau_h)?;
+
+    Ok(TuneResponse {
+        params: objective.params(&objective.best.x),
+        tau_h: objective.best.score,
+        default_tau_h,
+        gain: (default_tau_h > 0.).then(|| objective.best.score / default_tau_h - 1.),
+        evaluations: objective.evaluations,
+        results: objective.best.results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::test_data;
+
+    fn query(max_evals: u32) -> TuneQuery {
+        let mut settings = test_data(TheoryType::BT, "BT", 200.).settings;
+        settings.show_unofficials = true;
+        TuneQuery {
+            theory: TheoryType::BT,
+            strat: "BT".to_owned(),
+            sigma: 0,
+            custom_strat: Some(CustomStratQuery {
+                name: None,
+                conditions: IndexMap::from([("tai".to_owned(), "tai.cost + log10(weight + tai.level % 10) < rao.cost".to_owned())]),
+                params: IndexMap::new()
+            }),
+            params: IndexMap::from([("weight".to_owned(), ParamRange { min: 1., max: 20., default: Some(1.) })]),
+            rho: LogNum::from_log10(200.),
+            cap: LogNum::from_log10(250.),
+            step: LogNum::from_log10(25.),
+            max_evals: Some(max_evals),
+            settings
+        }
+    }
+
+    #[test]
+    fn improves_on_defaults() {
+        let res = tune(query(12)).unwrap();
+        assert!(res.evaluations <= 12);
+        assert!(res.tau_h >= res.default_tau_h);
+        assert_eq!(res.results.len(), 3);
+        let weight = res.params["weight"];
+        assert!((1. ..=20.).contains(&weight));
+    }
+
+    #[test]
+    fn builtin_strats_match_hardcoded() {
+        for (theory, strat, rho) in [(TheoryType::BaP, "BaPd", 50.), (TheoryType::BT, "BTd", 200.), (TheoryType::TC, "TCd", 100.)] {
+            let mut data = test_data(theory, strat, rho);
+            data.settings.show_unofficials = true;
+            let sim = |custom_strat| single_sim(SingleSimQuery {
+                theory,
+                strat: strat.to_owned(),
+                sigma: data.sigma,
+                rho: LogNum::from_log10(rho),
+                cap: None,
+                last_strat: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                custom_strat,
+                settings: data.settings.clone()
+            }).unwrap().result.tau_h;
+            let hardcoded = sim(None);
+            assert!((sim(builtin_strat(theory, strat)) - hardcoded).abs() < 1e-9 * hardcoded, "{strat}");
+        }
+    }
+
+    #[test]
+    fn tunes_builtin_weights() {
+        let mut q = query(4);
+        q.strat = "BTd".to_owned();
+        q.custom_strat = None;
+        q.params = IndexMap::from([("offset".to_owned(), ParamRange { min: 5., max: 15., default: None })]);
+        let res = tune(q).unwrap();
+        assert_eq!(res.evaluations, 4);
+        assert!(res.tau_h >= res.default_tau_h);
+        assert!(res.gain.is_some_and(|gain| gain >= 0.));
+    }
+
+    #[test]
+    fn invalid_queries() {
+        let mut q = query(5);
+        q.params.clear();
+        assert!(tune(q).is_err());
+        let mut q = query(5);
+        q.params["weight"].min = 30.;
+        assert!(tune(q).is_err());
+        let mut q = query(5);
+        q.custom_strat = None;
+        assert!(tune(q).is_err());
+        let mut q = query(0);
+        q.strat = "BTd".to_owned();
+        q.custom_strat = None;
+        q.params = IndexMap::from([("offset".to_owned(), ParamRange { min: 5., max: 15., default: None })]);
+        assert!(tune(q).is_err());
+    }
+}
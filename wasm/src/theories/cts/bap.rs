@@ -0,0 +1,636 @@
+/*!
+ * Basel Problem (BaP)
+ */
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+use indexmap::IndexMap;
+use num::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{config::TheoryType, error::SimError, query::PubTableQuery, response::{PubTableChange, PubTableResponse}};
+use crate::theories::theory::{Theory, TheoryBase, TheoryData};
+use crate::utils::{
+    binary_insertion_search,
+    cost::{Cost, ExponentialCost, FirstFreeCost},
+    lognum::{self, LogNum},
+    result::{SimResult, get_best_result, keep_best_result},
+    value::Value,
+    variable::Variable
+};
+
+/** Coast points (log10 of rho) of the semi-idle and AI strategies */
+const COAST_POINTS: [f64; 22] = [
+    10., 15., 20., 25., 30., 40., 50., 60., 70., 80., 100., 140., 150., 180., 200., 240., 300., 400., 500., 600., 700., 1000.
+];
+
+const A_POINTS: [f64; 9] = [20., 30., 50., 80., 140., 240., 400., 600., 800.];
+const Q_POINTS: [f64; 8] = [25., 40., 60., 100., 180., 300., 500., 700.];
+
+const A_PRIORITY: [usize; 5] = [0, 1, 2, 3, 4];
+const Q_PRIORITY: [usize; 5] = [0, 1, 3, 2, 4];
+
+/** Forced publication at which a continuation without forced publication is also simulated */
+const CONTINUITY_FORK_PUB: f64 = 1500.;
+
+/** Last publication of the final entry of the publication table */
+const PUB_TABLE_END: f64 = 1500.;
+/** Maximum number of publication table entries regenerated by a query, each one runs a sim */
+const MAX_PUB_TABLE_KEYS: usize = 200;
+
+/** Entry of the BaP publication table */
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+struct PubTableEntry {
+    /** rho (log10) of the next publication */
+    next: f64,
+    /** Time (seconds) needed to reach the end of the table from this publication */
+    time: f64
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PubTable<T> {
+    bapdata: T
+}
+
+static PUB_TABLE: OnceLock<Result<PubTable<HashMap<String, PubTableEntry>>, SimError>> = OnceLock::new();
+
+/** Returns the BaP publication table, indexed by the last publication */
+fn pub_table() -> Result<&'static HashMap<String, PubTableEntry>, SimError> {
+    PUB_TABLE
+        .get_or_init(|| {
+            serde_json::from_str(include_str!("../../../data/BaPpubtable.json"))
+                .map_err(|err| SimError::config(format!("Invalid BaP publication table: {err}")))
+        })
+        .as_ref()
+        .map(|table| &table.bapdata)
+        .map_err(SimError::clone)
+}
+
+/** Returns the publication forced by the publication table for a given last publication */
+fn get_forced_pub(last_pub: f64) -> Result<f64, SimError> {
+    if last_pub >= 1480. {
+        return Ok(f64::INFINITY);
+    }
+    let pub_seek = if last_pub < 100. { (last_pub * 4.).round() / 4. } else { last_pub.round() };
+    Ok(pub_table()?.get(&pub_seek.to_string())
+        .map_or(f64::INFINITY, |entry| entry.next))
+}
+
+/** Returns the keys (last publications) of the publication table, in increasing order */
+fn pub_table_keys() -> Vec<f64> {
+    (0..400).map(|i| i as f64 / 4.)
+        .chain((100..=PUB_TABLE_END as i32).map(f64::from))
+        .collect()
+}
+
+/**
+ * Regenerates the entries of the publication table whose key is in [`from`, `to`]
+ *
+ * For each key, a sim without forced publication gives the time needed to reach every following key,
+ * and the next publication is the one minimizing the time needed to reach the end of the table.
+ * Entries after `to` are taken from the committed table.
+ *
+ * Only the BaP table is generated. The CSR2, EF and FP tables, and their /8, /16 and /32 scaled
+ * variants, need the sims of these theories, which are not ported yet.
+ */
+pub fn generate_pub_table(query: PubTableQuery) -> Result<PubTableResponse, SimError> {
+    if query.theory != TheoryType::BaP {
+        return Err(SimError::invalid(format!("No publication table can be generated for {:?}, only BaP is supported", query.theory)));
+    }
+    let keys = pub_table_keys();
+    let committed = pub_table()?;
+    let from = query.from.unwrap_or(0.);
+    let to = query.to.unwrap_or(PUB_TABLE_END);
+    let key_count = keys.iter().filter(|&&key| from <= key && key <= to).count();
+    if key_count > MAX_PUB_TABLE_KEYS {
+        return Err(SimError::limit(format!(
+            "{key_count} publication table entries asked, at most {MAX_PUB_TABLE_KEYS} can be regenerated by one query"
+        )));
+    }
+
+    let mut table: Vec<PubTableEntry> = keys.iter()
+        .map(|key| committed.get(&key.to_string()).copied().unwrap_or(PubTableEntry { next: *key, time: f64::INFINITY }))
+        .collect();
+    let mut changes = Vec::new();
+
+    for i in (0..keys.len()).rev() {
+        if keys[i] < from || keys[i] > to {
+            continue;
+        }
+        let entry = if keys[i] >= PUB_TABLE_END {
+            PubTableEntry { next: keys[i], time: 0. }
+        } else {
+            let data = TheoryData {
+                theory: query.theory,
+                sigma: query.sigma,
+                rho: LogNum::from_log10(keys[i]),
+                strat: query.strat.clone(),
+                cap: None,
+                time_cap: None,
+                check_ins: None,
+                autobuy: false,
+                resume: None,
+                custom_strat: None,
+                settings: query.settings.clone()
+            };
+            let times = BapSim::new(&data)?.reach_times(&keys[i + 1..]);
+            times.iter().enumerate()
+                .map(|(j, time)| PubTableEntry { next: keys[i + 1 + j], time: time + table[i + 1 + j].time })
+                .min_by(|a, b| a.time.total_cmp(&b.time))
+                .filter(|entry| entry.time.is_finite())
+                .ok_or_else(|| SimError::numeric(format!("No publication found after {} for the BaP publication table", keys[i])))?
+        };
+
+        let old = committed.get(&keys[i].to_string());
+        if old.is_none_or(|old| old.next != entry.next) {
+            changes.push(PubTableChange {
+                key: keys[i],
+                old_next: old.map(|old| old.next),
+                new_next: entry.next,
+                old_time: old.map(|old| old.time),
+                new_time: entry.time
+            });
+        }
+        table[i] = entry;
+    }
+    changes.reverse();
+
+    let table: IndexMap<String, PubTableEntry> = keys.iter()
+        .zip(table)
+        .filter(|(_, entry)| entry.time.is_finite())
+        .map(|(key, entry)| (key.to_string(), entry))
+        .collect();
+
+    Ok(PubTableResponse {
+        theory: query.theory,
+        table: serde_json::to_value(PubTable { bapdata: table }).map_err(|err| SimError::numeric(err.to_string()))?,
+        changes
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BapStrat {
+    Idle,
+    Coast,
+    Ai,
+    AiMs,
+    AiMs2,
+    D,
+    DMs
+}
+
+impl BapStrat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "BaP" => Some(Self::Idle),
+            "BaPcoast" => Some(Self::Coast),
+            "BaPAI" => Some(Self::Ai),
+            "BaPAIMS" => Some(Self::AiMs),
+            "BaPAIMS2" => Some(Self::AiMs2),
+            "BaPd" => Some(Self::D),
+            "BaPdMS" => Some(Self::DMs),
+            _ => None
+        }
+    }
+
+    /** AI strategies buy variables with `buy_variables_weight` */
+    fn is_ai(self) -> bool {
+        matches!(self, Self::Ai | Self::AiMs | Self::AiMs2)
+    }
+
+    /** MS strategies swap the a and q milestones periodically */
+    fn swaps_milestones(self) -> bool {
+        matches!(self, Self::AiMs | Self::DMs)
+    }
+}
+
+/** Runs the BaP sim */
+pub fn bap(data: TheoryData) -> Result<SimResult, SimError> {
+    let mut sim = BapSim::new(&data)?;
+    let res = sim.simulate();
+    sim.base.custom_strat_result(res)
+}
+
+/** Returns the initial state of a BaP sim */
+pub fn base(data: &TheoryData) -> Result<TheoryBase, SimError> {
+    Ok(BapSim::new(data)?.base)
+}
+
+/** Returns rdot (`c1` is the log10 of c1) */
+fn get_rdot(c1: f64, r_ms: bool) -> LogNum {
+    if c1 <= 2. {
+        // Exact computation
+        let c1 = 10f64.powf(c1);
+        let mut sum = 0.;
+        let mut i = 1.;
+        while i < c1 + 0.001 {
+            sum += 1. / (i * i);
+            i += 1.;
+        }
+        return if r_ms {
+            LogNum::from(1. / (PI * PI / 6. - sum))
+        } else {
+            LogNum::from(sum + 1. / (c1 * c1))
+        };
+    }
+
+    let approx_sum = LogNum::from_log10(-c1) + LogNum::from_log10(-2f64.log10() - 2. * c1);
+    if r_ms {
+        if c1 <= 10. {
+            // Higher accuracy estimate
+            lognum::ONE / approx_sum
+        } else {
+            // Discards higher order terms to avoid dividing by 0
+            LogNum::from_log10(c1)
+        }
+    } else {
+        LogNum::from(PI * PI / 6.) - approx_sum + LogNum::from_log10(-2. * c1)
+    }
+}
+
+/** Returns the exponent a */
+fn get_a(level: i32, n_unlocked: bool, n_value: f64) -> f64 {
+    if n_unlocked {
+        let partial_sum = if n_value <= 100. {
+            // Exact computation
+            let mut sum = 0.;
+            let mut i = 1.;
+            while i <= n_value + 0.01 {
+                sum += 1. / (i * i);
+                i += 1.;
+            }
+            sum
+        } else {
+            PI * PI / 6. - (1. / (n_value + 1.) + 1. / (2. * (n_value + 1.) * (n_value + 1.)))
+        };
+        12. / (PI * PI) - 1. / partial_sum
+    } else {
+        let mut a = 0.3;
+        for i in (10 - level..=9).rev() {
+            a += (i * i) as f64 / 1000.;
+        }
+        a
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BapSim {
+    base: TheoryBase,
+    strat: BapStrat,
+    q: [LogNum; 9],
+    r: LogNum,
+    t_var: f64,
+    /** rho (log10) of the publication forced by the publication table */
+    forced_pub_rho: f64,
+    do_continuity_fork: bool,
+    best_res: Option<SimResult>
+}
+
+impl BapSim {
+    fn new(data: &TheoryData) -> Result<Self, SimError> {
+        let strat = BapStrat::from_name(&data.strat)
+            .ok_or_else(|| SimError::unknown_strategy(TheoryType::BaP, &data.strat))?;
+
+        let mut base = TheoryBase::new(data)?;
+        base.variables = vec![
+            Variable::new("tdot", Cost::new_exponential(1e6, 1e6), Value::new_stepwise(2., 10, 0.)),
+            Variable::new("c1", Cost::new(FirstFreeCost::new(ExponentialCost::new_log2(0.0625, 0.25))), Value::new_stepwise(65536., 64, 0.)),
+            Variable::new("c2", Cost::Exponential(ExponentialCost::new_log2(16., 4.)), Value::new_exponential(2.)),
+            Variable::new("c3", Cost::new_exponential(19683., 19683.), Value::new_exponential(3.))
+        ];
+        for i in 4..=10 {
+            let k = i as f64;
+            base.variables.push(Variable::new(
+                &format!("c{i}"),
+                Cost::Exponential(ExponentialCost::new_log2(k.powf(k * k), k * k * k.log2())),
+                Value::new_exponential(k)
+            ));
+        }
+        base.variables.push(Variable::new("n", Cost::Exponential(ExponentialCost::new_log2(1e40, 60. * 10f64.log2())), Value::new_stepwise(6., 16, 1.)));
+        base.milestone_unlocks = vec![
+            10., 15., 20., 25., 30., 40., 50., 70., 90., 120., 150., 200., 250., 300., 400., 500., 600., 700., 800., 1000.
+        ];
+
+        let mut sim = BapSim {
+            base,
+            strat,
+            q: [LogNum::from_log10(-1e60); 9],
+            r: LogNum::from_log10(-1e60),
+            t_var: 0.,
+            forced_pub_rho: get_forced_pub(data.rho.log10_f64())?,
+            do_continuity_fork: true,
+            best_res: None
+        };
+        // The total multiplier is computed before the publication unlock is set
+        sim.base.tot_mult = sim.get_tot_mult(data.rho);
+        sim.base.pub_unlock = 7.;
+        sim.update_milestones();
+        sim.base.validate_custom_strat()?;
+        if let Some(state) = &data.resume {
+            sim.resume(state)?;
+        }
+
+        Ok(sim)
+    }
+
+    /**
+     * Simulates without forced publication and returns the time at which each of `targets`
+     * (log10 of rho, in increasing order) is reached, infinite if it is not reached
+     */
+    fn reach_times(mut self, targets: &[f64]) -> Vec<f64> {
+        self.forced_pub_rho = f64::INFINITY;
+        self.do_continuity_fork = false;
+        let mut times = vec![f64::INFINITY; targets.len()];
+        let mut next = 0;
+        while next < targets.len() && !self.end_simulation() {
+            self.step();
+            while next < targets.len() && self.base.max_rho.log10_f64() >= targets[next] {
+                times[next] = self.base.t;
+                next += 1;
+            }
+        }
+        times
+    }
+
+    /** Runs one tick of the simulation loop */
+    fn step(&mut self) {
+        self.tick();
+        self.update_sim_status();
+        self.update_milestones();
+        if self.strat.is_ai() {
+            self.buy_variables_weight();
+        } else {
+            self.buy_variables();
+        }
+    }
+
+    fn get_next_coast(&self) -> f64 {
+        let rho = self.base.max_rho.max(self.base.last_pub).log10_f64();
+        COAST_POINTS.into_iter()
+            .find(|point| rho < *point)
+            .map_or(self.forced_pub_rho, |point| point.min(self.forced_pub_rho))
+    }
+
+    fn do_fork(&mut self) {
+        self.do_continuity_fork = false;
+        let mut fork = self.clone();
+        fork.forced_pub_rho = f64::INFINITY;
+        fork.best_res = None;
+        let res = fork.simulate();
+        keep_best_result(&mut self.best_res, res);
+    }
+}
+
+impl Theory for BapSim {
+    fn base(&self) -> &TheoryBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut TheoryBase {
+        &mut self.base
+    }
+
+    fn get_tot_mult(&self, rho: LogNum) -> LogNum {
+        if rho.log10_f64() < self.base.pub_unlock {
+            lognum::ONE
+        } else {
+            self.base.multiplier_at(rho)
+        }
+    }
+
+    fn set_currency(&mut self, name: &str, value: LogNum) -> Result<(), SimError> {
+        match name {
+            "r" => self.r = value,
+            "t" => self.t_var = 10f64.powf(value.log10_f64()),
+            _ => {
+                let i = name.strip_prefix('q')
+                    .and_then(|i| i.parse::<usize>().ok())
+                    .filter(|i| (1..=9).contains(i))
+                    .ok_or_else(|| SimError::invalid(format!("Unknown currency {name} for BaP")))?;
+                self.q[i - 1] = value;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_milestone_priority(&self) -> &'static [usize] {
+        if self.strat.swaps_milestones() && self.base.t % 300. < 100. {
+            &Q_PRIORITY
+        } else {
+            &A_PRIORITY
+        }
+    }
+
+    fn buying_condition(&self, id: usize) -> bool {
+        let vars = &self.base.variables;
+        match self.strat {
+            BapStrat::Coast => self.base.max_rho * LogNum::from(25.) < LogNum::from_log10(self.get_next_coast()),
+            BapStrat::D | BapStrat::DMs if id == 1 => {
+                vars[1].cost * LogNum::from(0.5 * vars[0].level as f64 % 64.) < vars[2].cost
+                    && (self.base.milestones[0] > 0 || vars[1].level < 65)
+            },
+            _ => true
+        }
+    }
+
+    fn variable_availability(&self, id: usize) -> bool {
+        match id {
+            0 => self.base.variables[0].level < 4,
+            3..=10 => self.base.milestones[3] > id as i32 - 3,
+            11 => self.base.milestones[4] > 0,
+            _ => true
+        }
+    }
+
+    fn get_variable_weights(&self) -> Vec<LogNum> {
+        let vars = &self.base.variables;
+        let next_coast = LogNum::from_log10(self.get_next_coast());
+        if self.base.max_rho * LogNum::from(25.) > next_coast {
+            return vec![LogNum::infinity(); vars.len()];
+        }
+
+        let min_layer_cost = vars[2..(self.base.milestones[4] + 3) as usize].iter()
+            .map(|var| var.cost)
+            .fold(LogNum::infinity(), LogNum::min);
+        let next_m64_levels = 64 - (vars[1].level - 1) % 64;
+        let p = 2f64.powf(0.25);
+        let next_m64_cost = vars[1].cost * LogNum::from((p.powi(next_m64_levels) - 1.) / (p - 1.));
+        let coast_64 = next_m64_cost < min_layer_cost * LogNum::from(2.) && self.base.milestones[0] > 0;
+
+        let mut weights = vec![lognum::ONE; vars.len()];
+        if coast_64 {
+            weights[2..11].fill(LogNum::from(4.));
+        } else {
+            weights[1] = if self.base.milestones[0] > 0 {
+                LogNum::from((vars[1].level % 64) as f64)
+            } else if vars[1].level < 65 {
+                LogNum::from(2.)
+            } else {
+                LogNum::infinity()
+            };
+        }
+        weights
+    }
+
+    fn evaluate_pub_conditions(&self) -> bool {
+        self.base.evaluate_pub_conditions() || self.base.max_rho.log10_f64() >= self.forced_pub_rho
+    }
+
+    fn do_sim_end_conditions(&self) -> bool {
+        self.forced_pub_rho == f64::INFINITY
+    }
+
+    fn update_milestones(&mut self) {
+        let rho = self.base.max_rho.max(self.base.last_pub).log10_f64();
+        let a_max = binary_insertion_search(&A_POINTS, rho);
+        let q_max = binary_insertion_search(&Q_POINTS, rho);
+        let use_n = if rho >= 1000. && self.base.max_rho.log10_f64() >= 940. { 1 } else { 0 };
+        self.base.milestones_max = vec![1, 1, a_max, q_max, use_n];
+
+        let priority = self.get_milestone_priority();
+        self.base.update_milestones(priority);
+    }
+
+    fn tick(&mut self) {
+        let dt = LogNum::from(self.base.dt);
+        let vars = &self.base.variables;
+        let milestones = &self.base.milestones;
+
+        self.t_var += (1 + vars[0].level) as f64 * self.base.dt;
+
+        if milestones[3] > 7 {
+            self.q[8] += vars[10].value * dt;
+        }
+        for i in (2..=9).rev() {
+            if milestones[3] > i as i32 - 3 {
+                let qdot = if milestones[3] > i as i32 - 2 { self.q[i - 1] } else { lognum::ONE };
+                self.q[i - 2] += vars[i].value * qdot * dt;
+            }
+        }
+
+        self.r += get_rdot(vars[1].value.log10_f64(), milestones[0] > 0) * dt;
+        let vn = if milestones[4] > 0 { 10f64.powf(vars[11].value.log10_f64()) } else { 0. };
+        let a = get_a(milestones[2], milestones[4] > 0, vn);
+
+        let rhodot = if milestones[1] == 0 {
+            self.base.tot_mult * (LogNum::from(self.t_var) * self.q[0] * self.r).powf64(a)
+        } else {
+            self.base.tot_mult * LogNum::from(self.t_var) * (self.q[0] * self.r).powf64(a)
+        };
+        self.base.rho.add(rhodot * dt);
+    }
+
+    fn simulate(&mut self) -> SimResult {
+        while !self.end_simulation() {
+            self.step();
+            if self.forced_pub_rho == CONTINUITY_FORK_PUB
+                && self.base.max_rho.log10_f64() >= 1495.
+                && self.do_continuity_fork
+            {
+                self.do_fork();
+            }
+        }
+        self.base.trim_bought_vars();
+
+        let result = self.create_result("");
+        match self.best_res.take() {
+            Some(best_res) => get_best_result(result, best_res),
+            None => result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::query::{ResumeState, SimQuery};
+    use crate::theories::test_data;
+
+    #[test]
+    fn forced_pub_from_table() {
+        assert_eq!(get_forced_pub(0.).unwrap(), 8.);
+        assert_eq!(get_forced_pub(1490.).unwrap(), f64::INFINITY);
+        let res = bap(test_data(TheoryType::BaP, "BaPd", 50.)).unwrap();
+        assert!((res.pub_rho.log10_f64() - get_forced_pub(50.).unwrap()).abs() < 0.1);
+    }
+
+    #[test]
+    fn resumes_currencies() {
+        let mut data = test_data(TheoryType::BaP, "BaPd", 50.);
+        let state = |currency: &str| ResumeState {
+            rho: LogNum::from_log10(45.),
+            max_rho: None,
+            currencies: [(currency.to_owned(), LogNum::from_log10(10.))].into_iter().collect(),
+            levels: [("c1".to_owned(), 200)].into_iter().collect(),
+            t: 3600.,
+            milestones: None
+        };
+        data.resume = Some(state("q9"));
+        let sim = BapSim::new(&data).unwrap();
+        assert_eq!(sim.q[8].log10_f64(), 10.);
+        assert_eq!(sim.base.variables[1].level, 200);
+        assert!(bap(data).unwrap().pub_rho.log10_f64() > 50.);
+
+        let mut data = test_data(TheoryType::BaP, "BaPd", 50.);
+        data.resume = Some(state("q10"));
+        assert!(bap(data).is_err());
+    }
+
+    #[test]
+    fn pub_table_end() {
+        let res = generate_pub_table(PubTableQuery {
+            theory: TheoryType::BaP,
+            strat: "BaPAI".to_owned(),
+            sigma: 0,
+            from: Some(1497.),
+            to: None,
+            settings: test_data(TheoryType::BaP, "BaPAI", 0.).settings
+        }).unwrap();
+        let table = &res.table["bapdata"];
+        assert_eq!(table["1500"]["time"], 0.);
+        for key in ["1497", "1498", "1499"] {
+            assert!(table[key]["next"].as_f64().unwrap() > key.parse::<f64>().unwrap());
+            assert!(table[key]["time"].as_f64().unwrap() > 0.);
+        }
+        assert_eq!(table.as_object().unwrap().len(), pub_table().unwrap().len());
+        assert!(res.changes.iter().all(|change| change.key >= 1497.));
+    }
+
+    #[test]
+    fn pub_table_limits() {
+        let query = |theory, from| PubTableQuery {
+            theory,
+            strat: "BaPAI".to_owned(),
+            sigma: 0,
+            from,
+            to: None,
+            settings: test_data(TheoryType::BaP, "BaPAI", 0.).settings
+        };
+        assert!(matches!(generate_pub_table(query(TheoryType::BaP, None)), Err(SimError::LimitExceeded { .. })));
+        assert!(matches!(generate_pub_table(query(TheoryType::BaP, Some(1000.))), Err(SimError::LimitExceeded { .. })));
+        assert!(matches!(generate_pub_table(query(TheoryType::EF, Some(1497.))), Err(SimError::InvalidQuery { .. })));
+    }
+
+    #[test]
+    fn pub_table_query_tag() {
+        let settings = serde_json::json!({
+            "dt": 1.5, "ddt": 1.0001, "mf_reset_depth": 0, "bought_vars_delta": 5, "sim_all_strats": "all",
+            "completed_cts": "no", "show_a23": false, "show_unofficials": false
+        });
+        let query = serde_json::from_value(serde_json::json!({
+            "type": "pub_table",
+            "data": { "theory": "BaP", "strat": "BaPAI", "sigma": 0, "from": 1000, "to": 1010, "settings": settings }
+        })).unwrap();
+        assert!(matches!(query, SimQuery::PubTable(_)));
+    }
+
+    #[test]
+    fn continuity_fork() {
+        assert_eq!(get_forced_pub(1400.).unwrap(), CONTINUITY_FORK_PUB);
+        let res = bap(test_data(TheoryType::BaP, "BaPAIMS", 1400.)).unwrap();
+        assert!(res.pub_rho.log10_f64() > 1400.);
+        assert!(res.tau_h > 0.);
+    }
+}
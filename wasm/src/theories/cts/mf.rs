@@ -0,0 +1,621 @@
+/*!
+ * Magnetic Fields (MF)
+ */
+
+use std::f64::consts::PI;
+
+use num::Float;
+
+use crate::api::{config::TheoryType, error::SimError};
+use crate::theories::theory::{Theory, TheoryBase, TheoryData};
+use crate::utils::{
+    cost::{Cost, ExponentialCost, FirstFreeCost},
+    lognum::{self, LogNum},
+    result::{SimResult, get_best_result, keep_best_result},
+    value::Value,
+    var_buy::VarBuy,
+    variable::Variable
+};
+
+/** Levels of v1, v2, v3 and v4 bought before a particle reset */
+type ResetBundle = [i32; 4];
+
+/** Reset bundles simulated for every MF sim */
+const RESET_BUNDLES: [ResetBundle; 3] = [
+    [0, 1, 0, 0],
+    [0, 1, 0, 1],
+    [0, 2, 0, 0]
+];
+
+/**
+ * Distance (log10 of rho) to the last publication under which extra resets are explored,
+ * indexed by `SimSettings.mf_reset_depth`
+ */
+const DEPTH_CONVERT: [f64; 6] = [-99999., 8., 15., 25., 35., 45.];
+
+const MU0: f64 = 4e-7 * PI;
+const Q0: f64 = 1.602e-19;
+const I0: f64 = 1e-15;
+const M0: f64 = 1e-3;
+const Q0_M0_MU0: f64 = (Q0 / M0) * MU0;
+
+/** Weights of δ in d2 strategies, indexed by the omega exponent milestone */
+const D_POWER: [f64; 3] = [3.09152, 3.00238, 2.91940];
+
+/** Strategies of MF, as named in data.json */
+const STRAT_NAMES: [&str; 31] = [
+    "MF", "MFCoast", "MFRC", "MFRCCoast",
+    "MFd", "MFdCoast", "MFdRCCoast", "MFd2", "MFd2Coast", "MFd2RCCoast", "MFd3", "MFd3Coast", "MFd3RCCoast",
+    "MFVariantd1d1d2Coast", "MFVariantd1d1d2RCCoast", "MFVariantd1d1d3Coast", "MFVariantd1d1d3RCCoast",
+    "MFVariantd1d2d1Coast", "MFVariantd1d2d1RCCoast", "MFVariantd1d2d2Coast", "MFVariantd1d2d2RCCoast",
+    "MFVariantd2d1d1Coast", "MFVariantd2d1d1RCCoast", "MFVariantd2d1d2Coast", "MFVariantd2d1d2RCCoast",
+    "MFVariantd2d1d3Coast", "MFVariantd2d1d3RCCoast", "MFVariantd2d2d1Coast", "MFVariantd2d2d1RCCoast",
+    "MFVariantd2d2d3Coast", "MFVariantd2d2d3RCCoast"
+];
+
+/** Buying behaviour of c1, a1 or δ in a MF strategy */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MfBuy {
+    Idle,
+    D1,
+    D2,
+    D3
+}
+
+/** Buying behaviour of a MF strategy */
+#[derive(Debug, Clone, Copy)]
+struct MfStrat {
+    c1: MfBuy,
+    a1: MfBuy,
+    delta: MfBuy,
+    /** For RC strategies, c1 is only bought if it costs less than the next reset divided by this margin */
+    c1_reset_margin: Option<f64>,
+    /** Coast strategies stop buying c1 at the end of the publication */
+    coast: bool
+}
+
+impl MfStrat {
+    /** Parses a strategy name such as `MFd2RCCoast` or `MFVariantd1d2d1Coast` */
+    fn from_name(name: &str) -> Option<Self> {
+        use MfBuy::*;
+
+        if !STRAT_NAMES.contains(&name) {
+            return None;
+        }
+        let coast = name.ends_with("Coast");
+        let name = name.strip_suffix("Coast").unwrap_or(name);
+        let (name, rc) = match name.strip_suffix("RC") {
+            Some(name) => (name, true),
+            None => (name, false)
+        };
+
+        let (c1, a1, delta) = match name {
+            "MF" => (Idle, Idle, Idle),
+            "MFd" => (D1, D1, D1),
+            "MFd2" => (D2, D2, D2),
+            "MFd3" => (D1, D2, D3),
+            _ => {
+                let kinds = name.strip_prefix("MFVariantd")?
+                    .split('d')
+                    .map(|kind| match kind {
+                        "1" => Some(D1),
+                        "2" => Some(D2),
+                        "3" => Some(D3),
+                        _ => None
+                    })
+                    .collect::<Option<Vec<MfBuy>>>()?;
+                let [c1, a1, delta] = kinds[..] else { return None };
+                (c1, a1, delta)
+            }
+        };
+
+        let c1_reset_margin = rc.then_some(match (name, c1, a1) {
+            ("MF" | "MFd", _, _) => 2.,
+            ("MFd2", _, _) => 4. / 3.,
+            ("MFd3", _, _) | (_, D1, D1) => 5.,
+            (_, D1, _) => 2.,
+            _ => 4. / 3.
+        });
+
+        Some(MfStrat { c1, a1, delta, c1_reset_margin, coast })
+    }
+}
+
+/**
+ * Runs the MF sim for every reset bundle and returns the best result
+ *
+ * Coast strategies seed their last c1 level from a run at reset depth 0.
+ */
+pub fn mf(data: TheoryData) -> Result<SimResult, SimError> {
+    let mut best_res: Option<SimResult> = None;
+    for reset_bundle in RESET_BUNDLES {
+        if data.rho.log10_f64() <= 100. && reset_bundle[3] > 0 {
+            continue;
+        }
+        let mut sim = MfSim::new(&data, reset_bundle)?;
+        if sim.strat.coast && sim.mf_reset_depth > 0 {
+            let mut temp_sim = sim.clone();
+            temp_sim.mf_reset_depth = 0;
+            let temp_res = temp_sim.simulate();
+            if let Some(last_c1) = temp_res.strat.split("c1: ")
+                .nth(1)
+                .and_then(|s| s.split_whitespace().next())
+                .and_then(|s| s.parse().ok())
+            {
+                sim.last_c1 = Some(last_c1);
+            }
+        }
+        let res = sim.simulate();
+        let res = sim.base.custom_strat_result(res)?;
+        keep_best_result(&mut best_res, res);
+    }
+    Ok(best_res.unwrap_or_default())
+}
+
+/** Returns the initial state of an MF sim, with the first reset bundle */
+pub fn base(data: &TheoryData) -> Result<TheoryBase, SimError> {
+    Ok(MfSim::new(data, RESET_BUNDLES[0])?.base)
+}
+
+#[derive(Debug, Clone)]
+struct MfSim {
+    base: TheoryBase,
+    strat: MfStrat,
+    /** Maximum c1 level for coast strategies */
+    last_c1: Option<i32>,
+    fork_on_c1: bool,
+    c: LogNum,
+    x: f64,
+    i: f64,
+    vx: f64,
+    vz: f64,
+    vtot: f64,
+    resets: i32,
+    stop_reset: bool,
+    reset_bundle: ResetBundle,
+    goal_bundle: ResetBundle,
+    goal_bundle_cost: LogNum,
+    mf_reset_depth: usize,
+    // Values only depending on variable levels and milestones, precomputed to lighten the tick function
+    precomp_omegaexp: f64,
+    precomp_xexp: f64,
+    precomp_vexp: f64,
+    precomp_a1exp: f64,
+    precomp_vterm: LogNum,
+    precomp_va1: f64,
+    precomp_va2: f64,
+
+    best_res: Option<SimResult>
+}
+
+impl MfSim {
+    fn new(data: &TheoryData, reset_bundle: ResetBundle) -> Result<Self, SimError> {
+        let strat = MfStrat::from_name(&data.strat)
+            .ok_or_else(|| SimError::unknown_strategy(TheoryType::MF, &data.strat))?;
+        let mf_reset_depth = usize::try_from(data.settings.mf_reset_depth).ok()
+            .filter(|depth| *depth < DEPTH_CONVERT.len())
+            .ok_or_else(|| SimError::invalid(format!("Invalid MF reset depth {}", data.settings.mf_reset_depth)))?;
+
+        let mut base = TheoryBase::new(data)?;
+        base.variables = vec![
+            Variable::new("c1", Cost::new(FirstFreeCost::new(ExponentialCost::new(10., 2.))), Value::new_stepwise(2., 7, 0.)),
+            Variable::new("c2", Cost::new_exponential(1e3, 50.), Value::new_exponential(2.)),
+            Variable::new("a1", Cost::new_exponential(1e3, 25.), Value::new_stepwise(2., 5, 3.)),
+            Variable::new("a2", Cost::new_exponential(1e4, 100.), Value::new_exponential(1.25)),
+            Variable::new("δ", Cost::new_exponential(1e50, 300.), Value::new_exponential(1.1)),
+            Variable::new("v1", Cost::new_exponential(80., 80.), Value::new_stepwise(2., 10, 1.)),
+            Variable::new("v2", Cost::new_exponential(1e4, 10f64.powf(4.5)), Value::new_exponential(1.3)),
+            Variable::new("v3", Cost::new_exponential(1e50, 70.), Value::new_stepwise(2., 10, 0.)),
+            Variable::new("v4", Cost::new_exponential(1e52, 1e6), Value::new_exponential(1.5))
+        ];
+        base.milestone_unlocks = vec![20., 50., 175., 225., 275., 325., 425., 475., 525.];
+        base.milestones_max = vec![1, 1, 2, 2, 2, 1];
+
+        let mut sim = MfSim {
+            precomp_va2: 10f64.powf(base.variables[3].value.log10_f64()),
+            base,
+            strat,
+            last_c1: None,
+            fork_on_c1: false,
+            c: lognum::ONE,
+            x: 0.,
+            i: 0.,
+            vx: 0.,
+            vz: 0.,
+            vtot: 0.,
+            resets: 0,
+            stop_reset: false,
+            reset_bundle,
+            goal_bundle: [0; 4],
+            goal_bundle_cost: lognum::ONE,
+            mf_reset_depth,
+            precomp_omegaexp: 0.,
+            precomp_xexp: 0.,
+            precomp_vexp: 0.,
+            precomp_a1exp: 0.,
+            precomp_vterm: lognum::ONE,
+            precomp_va1: 0.,
+            best_res: None
+        };
+        // The total multiplier is computed before the publication unlock is set
+        sim.base.tot_mult = sim.get_tot_mult(data.rho);
+        sim.base.pub_unlock = 8.;
+        sim.update_milestones_no_ms();
+        sim.precompute_exps();
+        sim.reset_particle();
+        sim.base.validate_custom_strat()?;
+        if let Some(state) = &data.resume {
+            sim.resume(state)?;
+        }
+
+        Ok(sim)
+    }
+
+    /** Creates a copy of the sim to explore a different path */
+    fn fork(&self) -> Self {
+        let mut fork = self.clone();
+        fork.fork_on_c1 = false;
+        fork.best_res = None;
+        fork
+    }
+
+    fn record_fork(&mut self, res: SimResult) {
+        keep_best_result(&mut self.best_res, res);
+    }
+
+    fn precompute_exps(&mut self) {
+        let milestones = &self.base.milestones;
+        self.precomp_a1exp = 1. + 0.01 * milestones[5] as f64;
+        self.precomp_vexp = 1.3 + 0.31 * milestones[4] as f64;
+        self.precomp_xexp = 3.2 + 0.1 * milestones[3] as f64;
+        self.precomp_omegaexp = 4.1 + 0.15 * milestones[2] as f64;
+        self.compute_vterm();
+        self.compute_va1();
+    }
+
+    fn compute_vterm(&mut self) {
+        self.precomp_vterm = if self.base.milestones[0] > 0 {
+            LogNum::from(self.vtot).powf64(self.precomp_vexp)
+        } else {
+            lognum::ONE
+        };
+    }
+
+    fn compute_va1(&mut self) {
+        self.precomp_va1 = 10f64.powf(self.base.variables[2].value.log10_f64() * self.precomp_a1exp);
+    }
+
+    fn update_c(&mut self) {
+        let xterm = 4e13f64.log10() * self.precomp_xexp;
+        let omegaterm = ((M0 / (Q0 * MU0 * I0)).log10() - 900f64.log10()) * self.precomp_omegaexp;
+        let vterm = if self.base.milestones[0] > 0 {
+            3e19f64.log10() * 1.3 + 1e5f64.log10() * (self.precomp_vexp - 1.3)
+        } else {
+            0.
+        };
+        self.c = LogNum::from_log10(xterm + omegaterm + vterm + 8.67e23f64.log10());
+    }
+
+    /** Restarts the particle with the current v1-v4 levels, without counting a reset */
+    fn restart_particle(&mut self) {
+        let vars = &self.base.variables;
+        self.x = 0.;
+        self.vx = 10f64.powf(vars[5].value.log10_f64() + vars[6].value.log10_f64() - 20.);
+        self.vz = 10f64.powf(vars[7].value.log10_f64() + vars[8].value.log10_f64() - 18.);
+        self.vtot = (self.vx * self.vx + self.vz * self.vz).sqrt();
+        self.compute_vterm();
+        self.goal_bundle = self.get_goal_bundle(self.reset_bundle);
+        self.goal_bundle_cost = self.calc_bundle_cost(self.goal_bundle);
+    }
+
+    fn reset_particle(&mut self) {
+        self.resets += 1;
+        if self.resets > 1 {
+            let vars = &self.base.variables;
+            self.base.bought_vars.push(VarBuy {
+                var_name: format!("Reset at V={},{},{},{}", vars[5].level, vars[6].level, vars[7].level, vars[8].level),
+                level: self.resets - 1,
+                cost: self.base.max_rho,
+                symbol: self.base.rho.symbol.clone(),
+                timestamp: self.base.t
+            });
+        }
+        self.restart_particle();
+    }
+
+    /** Cost of buying the levels of the bundle for v1-v4 */
+    fn calc_bundle_cost(&self, bundle: ResetBundle) -> LogNum {
+        let mut cost = lognum::ONE;
+        for (i, levels) in bundle.into_iter().enumerate() {
+            if levels == 0 {
+                continue;
+            }
+            let var = &self.base.variables[5 + i];
+            cost += var.get_cost_for_levels(var.level, var.level + levels);
+        }
+        cost
+    }
+
+    /** Extends `bundle` with all v levels that are cheap compared to the bundle */
+    fn get_goal_bundle(&self, bundle: ResetBundle) -> ResetBundle {
+        let mut goal_bundle = bundle;
+        if self.base.max_rho.log10_f64() <= 65. {
+            goal_bundle[2] = 0;
+            goal_bundle[3] = 0;
+        }
+
+        let margin = LogNum::from_log10(0.01);
+        for i in [1, 3, 0, 2] {
+            let bundle_cost = self.calc_bundle_cost(goal_bundle);
+            let var = &self.base.variables[5 + i];
+            while var.get_cost_for_level(var.level + goal_bundle[i]) < bundle_cost * margin {
+                goal_bundle[i] += 1;
+            }
+        }
+        goal_bundle
+    }
+
+    /** Returns true if a purchase of `cost` leaves enough rho for the next reset in RC strategies */
+    fn below_reset_cost(&self, cost: LogNum, margin: Option<f64>) -> bool {
+        match margin {
+            Some(margin) => self.stop_reset || cost < self.goal_bundle_cost / LogNum::from(margin),
+            None => true
+        }
+    }
+
+    fn test_final_reset(&mut self) {
+        let mut fork = self.fork();
+        fork.stop_reset = true;
+        let res = fork.simulate();
+        self.record_fork(res);
+    }
+
+    fn check_for_reset(&mut self) {
+        let depth = DEPTH_CONVERT[self.mf_reset_depth];
+        self.buy_v_variables();
+        self.reset_particle();
+        if self.base.max_rho.log10_f64() >= self.base.last_pub.log10_f64() - 10. {
+            self.test_final_reset();
+        }
+        if depth > 0. && self.base.last_pub.log10_f64() - self.base.max_rho.log10_f64() <= depth {
+            // Extra v1 and v2 tests
+            for extra in [0, 1] {
+                let mut fork = self.fork();
+                let mut bundle = fork.goal_bundle;
+                bundle[extra] += 1;
+                fork.goal_bundle = fork.get_goal_bundle(bundle);
+                fork.goal_bundle_cost = fork.calc_bundle_cost(fork.goal_bundle);
+                let res = fork.simulate();
+                self.record_fork(res);
+            }
+        }
+    }
+
+    fn do_fork_c1(&mut self) {
+        let mut fork = self.fork();
+        fork.last_c1 = Some(self.base.variables[0].level);
+        let res = fork.simulate();
+        self.record_fork(res);
+        self.fork_on_c1 = false;
+    }
+
+    /** Buys c1, c2, a1, a2 and δ, v1-v4 are only bought at resets */
+    fn buy_normal_variables(&mut self) {
+        if !self.base.can_buy() {
+            return;
+        }
+        for i in (0..5).rev() {
+            while self.base.rho.value > self.base.variables[i].cost
+                && self.strat_buying_condition(i)
+                && self.variable_availability(i)
+            {
+                self.base.buy_variable(i);
+                self.on_variable_purchased(i);
+            }
+        }
+    }
+
+    /** Buys as many v1-v4 levels as possible */
+    fn buy_v_variables(&mut self) {
+        for i in (5..9).rev() {
+            while self.base.rho.value > self.base.variables[i].cost && self.variable_availability(i) {
+                self.base.buy_variable(i);
+            }
+        }
+    }
+}
+
+impl Theory for MfSim {
+    fn base(&self) -> &TheoryBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut TheoryBase {
+        &mut self.base
+    }
+
+    fn get_tot_mult(&self, rho: LogNum) -> LogNum {
+        if rho.log10_f64() < self.base.pub_unlock {
+            lognum::ONE
+        } else {
+            self.base.multiplier_at(rho)
+        }
+    }
+
+    fn get_milestone_priority(&self) -> &'static [usize] {
+        &[0, 1, 2, 3, 4, 5]
+    }
+
+    fn buying_condition(&self, id: usize) -> bool {
+        let vars = &self.base.variables;
+        let milestones = &self.base.milestones;
+        let d_power = LogNum::from(D_POWER[milestones[2] as usize]);
+        match id {
+            0 => {
+                let c1 = &vars[0];
+                self.below_reset_cost(c1.cost, self.strat.c1_reset_margin)
+                    && self.last_c1.is_none_or(|last_c1| c1.level < last_c1)
+                    && match self.strat.c1 {
+                        MfBuy::Idle => true,
+                        MfBuy::D2 => {
+                            let delta_cost = if milestones[1] > 0 { vars[4].cost * d_power } else { LogNum::from_log10(f64::INFINITY) };
+                            c1.cost * LogNum::from((8 + c1.level % 7) as f64)
+                                <= (vars[1].cost * LogNum::from(2.)).min(vars[3].cost).min(delta_cost)
+                        },
+                        _ => c1.cost * LogNum::from(9.9) <= vars[1].cost.min(vars[3].cost).min(vars[4].cost)
+                    }
+            },
+            2 => {
+                let reset_margin = self.strat.c1_reset_margin.map(|_| 10.);
+                let a2_log = vars[3].value.log10_f64();
+                self.below_reset_cost(vars[2].cost, reset_margin)
+                    && match self.strat.a1 {
+                        MfBuy::Idle => true,
+                        MfBuy::D1 => self.i / (I0 * self.precomp_va2) < 0.5 || vars[2].cost * LogNum::from(10.) < self.base.max_rho,
+                        _ => (self.i * 1.2).log10() < a2_log - 15.
+                            || (vars[2].cost * LogNum::from(20.) < self.base.max_rho && (self.i * 1.012).log10() < a2_log - 15.)
+                    }
+            },
+            4 => match self.strat.delta {
+                MfBuy::Idle => true,
+                MfBuy::D1 => vars[4].cost < vars[1].cost.min(vars[3].cost),
+                MfBuy::D2 => vars[4].cost * d_power < (vars[1].cost * LogNum::from(2.)).min(vars[3].cost),
+                MfBuy::D3 => vars[4].cost < (vars[3].cost * LogNum::from(0.6)).min(vars[1].cost * LogNum::from(0.75))
+            },
+            _ => true
+        }
+    }
+
+    fn variable_availability(&self, id: usize) -> bool {
+        match id {
+            4 => self.base.milestones[1] > 0,
+            7 | 8 => self.base.milestones[0] > 0,
+            _ => true
+        }
+    }
+
+    fn on_variable_purchased(&mut self, id: usize) {
+        if id == 2 {
+            self.compute_va1();
+        }
+        if id == 3 {
+            self.precomp_va2 = 10f64.powf(self.base.variables[3].value.log10_f64());
+        }
+        if self.mf_reset_depth == 0
+            && self.strat.coast
+            && id == 0
+            && self.last_c1.is_none()
+            && self.base.max_rho.log10_f64() > self.base.last_pub.log10_f64() + 6.
+        {
+            self.fork_on_c1 = true;
+        }
+    }
+
+    /** The particle is restarted from a reset, its previous position is not part of the in-game state */
+    fn on_resume(&mut self) {
+        self.update_milestones_no_ms();
+        self.precomp_va2 = 10f64.powf(self.base.variables[3].value.log10_f64());
+        self.restart_particle();
+    }
+
+    fn update_milestones_no_ms(&mut self) -> bool {
+        let updated = self.base.update_milestones_no_ms(self.get_milestone_priority());
+        if updated {
+            self.precompute_exps();
+            self.update_c();
+        }
+        updated
+    }
+
+    fn tick(&mut self) {
+        let dt = self.base.dt;
+
+        // Current in the coil
+        let icap = self.precomp_va2 * I0;
+        if self.i < icap {
+            let rate = dt * self.precomp_va1 / (400. * self.precomp_va2);
+            let mut scale = 1. - (-rate).exp();
+            if scale < 1e-13 {
+                scale = rate;
+            }
+            self.i = (self.i + scale * (icap - self.i)).min(icap);
+        }
+
+        self.x += dt * self.vx;
+        let vars = &self.base.variables;
+        let xterm = LogNum::from(self.x).powf64(self.precomp_xexp);
+        let omegaterm = (LogNum::from(Q0_M0_MU0 * self.i) * vars[4].value).powf64(self.precomp_omegaexp);
+
+        let rhodot = self.base.tot_mult * self.c * vars[0].value * vars[1].value * xterm * omegaterm * self.precomp_vterm;
+        self.base.rho.add(rhodot * LogNum::from(dt));
+    }
+
+    fn simulate(&mut self) -> SimResult {
+        let reset_margin = LogNum::from_log10(0.0001);
+        while !self.end_simulation() {
+            self.tick();
+            self.update_sim_status();
+            self.update_milestones_no_ms();
+            self.buy_normal_variables();
+            if !self.stop_reset && self.base.can_buy() && self.base.rho.value >= self.goal_bundle_cost * reset_margin {
+                self.check_for_reset();
+            }
+            if self.fork_on_c1 {
+                self.do_fork_c1();
+            }
+        }
+        self.base.trim_bought_vars();
+
+        let mut strat_extra = format!(" Depth: {}", self.mf_reset_depth);
+        if let Some(last_c1) = self.last_c1 {
+            strat_extra += &format!(" c1: {last_c1}");
+        }
+        let result = self.create_result(&strat_extra);
+        match self.best_res.take() {
+            Some(best_res) => get_best_result(result, best_res),
+            None => result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::test_data;
+
+    fn data(strat: &str, rho: f64, depth: i32) -> TheoryData {
+        let mut data = test_data(TheoryType::MF, strat, rho);
+        data.settings.mf_reset_depth = depth;
+        data
+    }
+    #[test]
+    fn strat_names() {
+        assert!(MfStrat::from_name("MFVariantd2d1d3RCCoast").is_some());
+        assert!(MfStrat::from_name("MFVariantd3d1d1Coast").is_none());
+        assert!(MfStrat::from_name("MFd4").is_none());
+        assert!(MfStrat::from_name("MFVariantd1d1d2d2").is_none());
+        assert!(MfStrat::from_name("MFVariantxd1d1d2").is_none());
+        assert!(MfStrat::from_name("MFVariantd1d1").is_none());
+        for name in ["MFVariantd1d1d1Coast", "MFVariantd2d2d2Coast", "MFVariantd1d2d1", "MFdRC", "MFd2RC", "MFd3RC"] {
+            assert!(MfStrat::from_name(name).is_none(), "{name}");
+        }
+        for name in STRAT_NAMES {
+            assert!(MfStrat::from_name(name).is_some(), "{name}");
+        }
+        assert_eq!(MfStrat::from_name("MFVariantd1d2d1RCCoast").unwrap().c1_reset_margin, Some(2.));
+    }
+
+    #[test]
+    fn sim_progresses() {
+        let res = mf(data("MFd2RCCoast", 300., 0)).unwrap();
+        assert!(res.pub_rho.log10_f64() > 300.);
+        assert!(res.tau_h > 0.);
+        assert!(res.strat.starts_with("MFd2RCCoast Depth: 0"));
+    }
+
+    #[test]
+    fn unknown_strat() {
+        assert!(mf(data("MFx", 300., 0)).is_err());
+        assert!(mf(data("MF", 300., 6)).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+/*! Custom theories */
+
+pub mod mf;
+pub mod bap;
+pub mod rz_zeros;
\ No newline at end of file
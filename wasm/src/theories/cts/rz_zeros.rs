@@ -0,0 +1,196 @@
+/*!
+ * Generator of the good zeros table of Riemann Zeta Function (RZ)
+ *
+ * Black hole strategies of RZ stop t on a zero of zeta, where the growth of delta is driven by
+ * the modulus of the derivative of zeta. A zero is proposed when its derivative is at least
+ * `min_ratio` times the derivative of every zero before it, since reaching it takes longer.
+ *
+ * The committed table was picked by hand from RZ sims, which are not ported, so the generator only
+ * approximates it. Over [200, 1300] it proposes 201.26, 207.91, 227.42, 253.07, 433.89 and 635.52,
+ * which are not in the table, and misses 297.98 and 399.99, whose derivatives are a few percent
+ * below the record. The regenerated table is a starting point to check with sims, not a drop-in.
+ */
+
+use std::sync::OnceLock;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{error::SimError, query::RzZerosQuery, response::RzZerosResponse};
+use crate::utils::zeta;
+
+/** Default number of grid points per average zero spacing used to bracket zeros */
+const DEFAULT_RESOLUTION: u32 = 8;
+/** Maximum number of grid points per average zero spacing */
+const MAX_RESOLUTION: u32 = 64;
+/** Maximum number of Riemann-Siegel terms evaluated by a query, a few seconds of work */
+const MAX_TERMS: f64 = 2e8;
+
+/** Range of t covered by a set of zeros for a given rho */
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ZeroBoundary {
+    pub to_rho: f64,
+    pub from: f64,
+    pub to: f64
+}
+
+/** Contents of `RZgoodzeros.json` */
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GoodZeros {
+    pub generic_zeros: Vec<f64>,
+    pub long_zeros: Vec<f64>,
+    pub rz_specific_zeros: Vec<f64>,
+    pub rzd_specific_zeros: Vec<f64>,
+    pub rz_rewind: Vec<f64>,
+    pub rz_rewind_boundaries: Vec<ZeroBoundary>,
+    #[serde(rename = "rzIdleBHBoundaries")]
+    pub rz_idle_bh_boundaries: Vec<ZeroBoundary>,
+    pub rzd_idle_boundaries: Vec<ZeroBoundary>
+}
+
+/** Zero of zeta ranked against the zeros before it */
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RankedZero {
+    /** Position of the zero on the critical line */
+    pub t: f64,
+    /** Modulus of the derivative of zeta at the zero */
+    pub deriv: f64,
+    /** `deriv` divided by the largest derivative of the zeros before it */
+    pub ratio: f64
+}
+
+static GOOD_ZEROS: OnceLock<Result<GoodZeros, SimError>> = OnceLock::new();
+
+/** Returns the good zeros table currently shipped with the sim */
+pub fn good_zeros() -> Result<&'static GoodZeros, SimError> {
+    GOOD_ZEROS
+        .get_or_init(|| {
+            serde_json::from_str(include_str!("../../../data/RZgoodzeros.json"))
+                .map_err(|err| SimError::config(format!("Invalid RZ good zeros table: {err}")))
+        })
+        .as_ref()
+        .map_err(SimError::clone)
+}
+
+/** Rounds a zero the way it is written in the table */
+fn round_zero(t: f64) -> f64 {
+    (t * 100.).round() / 100.
+}
+
+/**
+ * Finds the zeros of zeta in [`from`, `to`] and ranks them
+ *
+ * `best_deriv` is the largest derivative of the zeros before `from`.
+ */
+pub fn rank_zeros(from: f64, to: f64, resolution: u32, mut best_deriv: f64) -> Vec<RankedZero> {
+    zeta::find_zeros(from, to, resolution).into_iter()
+        .map(|t| {
+            let deriv = zeta::zeta_deriv_norm(t);
+            let ratio = if best_deriv > 0. { deriv / best_deriv } else { f64::INFINITY };
+            best_deriv = best_deriv.max(deriv);
+            RankedZero { t, deriv, ratio }
+        })
+        .collect()
+}
+
+/**
+ * Regenerates the generic zeros of `base` in [`from`, `to`] from the zeros with a ratio of at least `min_ratio`
+ *
+ * Generic zeros outside of the range are kept.
+ */
+pub fn regenerate_table(base: &GoodZeros, zeros: &[RankedZero], from: f64, to: f64, min_ratio: f64) -> GoodZeros {
+    let mut generic_zeros: Vec<f64> = base.generic_zeros.iter()
+        .copied()
+        .filter(|&t| t < from || t > to)
+        .chain(zeros.iter().filter(|zero| zero.ratio >= min_ratio).map(|zero| round_zero(zero.t)))
+        .collect();
+    generic_zeros.sort_by(f64::total_cmp);
+    generic_zeros.dedup();
+
+    GoodZeros { generic_zeros, ..base.clone() }
+}
+
+/** Answers a good zeros query */
+pub fn rz_zeros(query: RzZerosQuery) -> Result<RzZerosResponse, SimError> {
+    if !(query.from.is_finite() && query.to.is_finite()) || query.from < 1. || query.to <= query.from {
+        return Err(SimError::invalid(format!("Invalid range of t for RZ zeros: [{}, {}]", query.from, query.to)));
+    }
+    let resolution = query.resolution.unwrap_or(DEFAULT_RESOLUTION);
+    if resolution == 0 {
+        return Err(SimError::invalid("The resolution of the RZ zeros search must be at least 1"));
+    }
+    if resolution > MAX_RESOLUTION {
+        return Err(SimError::limit(format!("The resolution of the RZ zeros search is at most {MAX_RESOLUTION}")));
+    }
+    // Every grid point evaluates Z, whose Riemann-Siegel sum has sqrt(t / 2pi) terms
+    let points = (query.to - query.from) * resolution as f64 / zeta::zero_spacing(query.to);
+    if points * (query.to / std::f64::consts::TAU).sqrt() > MAX_TERMS {
+        return Err(SimError::limit(format!("The range [{}, {}] is too large for one RZ zeros query", query.from, query.to)));
+    }
+    let min_ratio = query.min_ratio.unwrap_or(1.);
+    let base = good_zeros()?;
+
+    // The zeros of the table before the range stand for the zeros that are not searched
+    let best_deriv = base.generic_zeros.iter()
+        .filter(|&&t| t < query.from)
+        .map(|&t| zeta::zeta_deriv_norm(t))
+        .fold(0., f64::max);
+    let zeros = rank_zeros(query.from, query.to, resolution, best_deriv);
+    let table = regenerate_table(base, &zeros, query.from, query.to, min_ratio);
+
+    Ok(RzZerosResponse {
+        zeros: zeros.into_iter().filter(|zero| zero.ratio >= min_ratio).collect(),
+        table
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_parses() {
+        let table = good_zeros().unwrap();
+        assert!(table.generic_zeros.contains(&263.57));
+        assert_eq!(table.rz_idle_bh_boundaries.len(), 11);
+    }
+
+    #[test]
+    fn regenerated_range_against_table() {
+        let in_range = |zeros: &[f64]| zeros.iter().copied().filter(|t| (200. ..=1300.).contains(t)).collect::<Vec<f64>>();
+        let committed = in_range(&good_zeros().unwrap().generic_zeros);
+        let regenerated = in_range(&rz_zeros(RzZerosQuery { from: 200., to: 1300., resolution: None, min_ratio: None }).unwrap().table.generic_zeros);
+
+        // Documented differences with the hand-picked table
+        let added: Vec<f64> = regenerated.iter().copied().filter(|t| !committed.contains(t)).collect();
+        let missing: Vec<f64> = committed.iter().copied().filter(|t| !regenerated.contains(t)).collect();
+        assert_eq!(added, [201.26, 207.91, 227.42, 253.07, 433.89, 635.52]);
+        assert_eq!(missing, [297.98, 399.99]);
+    }
+
+    #[test]
+    fn regenerate_keeps_outside_range() {
+        let res = rz_zeros(RzZerosQuery { from: 1000., to: 1300., resolution: None, min_ratio: None }).unwrap();
+        let generic = &res.table.generic_zeros;
+        assert!(generic.contains(&978.77));
+        assert!(generic.contains(&1287.41));
+        assert!(generic.contains(&964594.73));
+        assert!(generic.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(res.table.long_zeros, good_zeros().unwrap().long_zeros);
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert!(rz_zeros(RzZerosQuery { from: 500., to: 400., resolution: None, min_ratio: None }).is_err());
+        assert!(rz_zeros(RzZerosQuery { from: 400., to: 500., resolution: Some(0), min_ratio: None }).is_err());
+        let limited = [
+            RzZerosQuery { from: 400., to: 500., resolution: Some(MAX_RESOLUTION + 1), min_ratio: None },
+            RzZerosQuery { from: 1e6, to: 1e7, resolution: None, min_ratio: None }
+        ];
+        for query in limited {
+            assert!(matches!(rz_zeros(query), Err(SimError::LimitExceeded { .. })));
+        }
+    }
+}
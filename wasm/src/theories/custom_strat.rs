@@ -0,0 +1,163 @@
+/*!
+ * User-defined strategies
+ *
+ * A custom strategy gives the buying condition of some variables as expressions, which replace
+ * the buying conditions of the strategy being simulated. Variables without a custom condition keep
+ * the condition of the simulated strategy.
+ *
+ * Conditions can use, for each variable, `<name>.cost`, `<name>.value` (log10) and `<name>.level`,
+ * as well as `ms[i]` (milestone levels), `rho`, `maxRho`, `lastPub` (log10), `t` (seconds),
+ * `curMult` and `sigma`. Other identifiers are parameters, whose values are given with the strategy.
+ */
+
+use indexmap::IndexMap;
+
+use crate::api::{error::SimError, query::CustomStratQuery};
+use crate::theories::theory::TheoryBase;
+use crate::utils::expr::{self, Expr, ExprContext, ExprValue};
+
+/** Name given to custom strategies without a name */
+const DEFAULT_NAME: &str = "Custom";
+
+/** Custom strategy, compiled once per query */
+#[derive(Debug)]
+pub struct CustomStrat {
+    pub name: String,
+    /** Query the strategy was compiled from, which identifies it */
+    pub query: CustomStratQuery,
+    /** Buying conditions by (normalized) variable name */
+    conditions: IndexMap<String, Expr>,
+    /** Values of the parameters by (normalized) name */
+    params: IndexMap<String, f64>
+}
+
+impl CustomStrat {
+    /** Parses the conditions of a custom strategy */
+    pub fn compile(query: &CustomStratQuery) -> Result<Self, SimError> {
+        let conditions = query.conditions.iter()
+            .map(|(var, condition)| Ok((expr::normalize_ident(var), expr::parse(condition).map_err(SimError::parse)?)))
+            .collect::<Result<IndexMap<String, Expr>, SimError>>()?;
+
+        Ok(CustomStrat {
+            name: query.name.clone().unwrap_or(DEFAULT_NAME.to_owned()),
+            query: query.clone(),
+            conditions,
+            params: query.params.iter()
+                .map(|(name, value)| (expr::normalize_ident(name), *value))
+                .collect()
+        })
+    }
+
+    /**
+     * Checks that the conditions only use the variables of `base` and can be evaluated
+     *
+     * Must be called once the variables of the theory are created.
+     */
+    pub fn validate(&self, base: &TheoryBase) -> Result<(), SimError> {
+        for (var, condition) in &self.conditions {
+            if !base.variables.iter().any(|v| expr::normalize_ident(&v.name) == *var) {
+                return Err(SimError::invalid(format!("Unknown variable \"{var}\" in custom strategy {}", self.name)));
+            }
+            condition.eval(&StratContext { base, params: &self.params })
+                .map_err(|err| SimError::invalid(format!("Error in the condition of {var} in custom strategy {}: {err}", self.name)))?;
+        }
+        Ok(())
+    }
+
+    /** Returns the custom buying condition of the variable `id`, or None if it has none */
+    pub fn buying_condition(&self, base: &TheoryBase, id: usize) -> Option<Result<bool, SimError>> {
+        let var = expr::normalize_ident(&base.variables[id].name);
+        let condition = self.conditions.get(&var)?;
+        Some(condition.eval_bool(&StratContext { base, params: &self.params })
+            .map_err(|err| SimError::invalid(format!("Error in the condition of {var} in custom strategy {}: {err}", self.name))))
+    }
+}
+
+/** Exposes the state of a theory to the conditions of a custom strategy */
+struct StratContext<'a> {
+    base: &'a TheoryBase,
+    params: &'a IndexMap<String, f64>
+}
+
+impl ExprContext for StratContext<'_> {
+    fn get(&self, name: &str) -> Option<ExprValue> {
+        let base = self.base;
+        let value = match name {
+            "rho" => base.rho.value.log10_f64(),
+            "maxrho" => base.max_rho.log10_f64(),
+            "lastpub" => base.last_pub.log10_f64(),
+            "t" => base.t,
+            "curmult" => base.cur_mult,
+            "sigma" => base.sigma as f64,
+            _ => {
+                if let Some(param) = self.params.get(name) {
+                    return Some(ExprValue::Number(*param));
+                }
+                let (var, field) = name.split_once('.')?;
+                let var = base.variables.iter().find(|v| expr::normalize_ident(&v.name) == var)?;
+                match field {
+                    "cost" => var.cost.log10_f64(),
+                    "value" => var.value.log10_f64(),
+                    "level" => var.level as f64,
+                    _ => return None
+                }
+            }
+        };
+        Some(ExprValue::Number(value))
+    }
+
+    fn get_index(&self, name: &str, index: usize) -> Option<ExprValue> {
+        match name {
+            "ms" | "milestones" => self.base.milestones.get(index).map(|&level| ExprValue::Number(level as f64)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::api::config::TheoryType;
+    use crate::theories::{test_data, unofficial_cts::bt::bt};
+
+    fn custom_data(strat: &str, conditions: &[(&str, &str)]) -> Result<crate::theories::theory::TheoryData, SimError> {
+        let mut data = test_data(TheoryType::BT, strat, 200.);
+        let custom_strat = CustomStrat::compile(&CustomStratQuery {
+            name: Some("Test".to_owned()),
+            conditions: conditions.iter().map(|(var, cond)| (var.to_string(), cond.to_string())).collect(),
+            params: IndexMap::from([("offset".to_owned(), 10.)])
+        })?;
+        data.custom_strat = Some(Arc::new(custom_strat));
+        Ok(data)
+    }
+
+    #[test]
+    fn matches_hardcoded_strat() {
+        let hardcoded = bt(test_data(TheoryType::BT, "BTd", 200.)).unwrap();
+        let custom = bt(custom_data("BT", &[
+            ("tai", "tai.cost + log10((lastPub < 275 ? offset + 2 : offset) + tai.level % 10) < rao.cost")
+        ]).unwrap()).unwrap();
+        assert_eq!(custom.strat, "BT custom: Test");
+        assert!((custom.tau_h - hardcoded.tau_h).abs() < 1e-9 * hardcoded.tau_h);
+    }
+
+    #[test]
+    fn overrides_only_listed_variables() {
+        let res = bt(custom_data("BT", &[("tay", "false")]).unwrap()).unwrap();
+        assert!(res.bought_vars.iter().all(|buy| buy.var_name != "tay"));
+        assert!(res.bought_vars.iter().any(|buy| buy.var_name == "rao"));
+    }
+
+    #[test]
+    fn invalid_strats() {
+        assert!(custom_data("BT", &[("tai", "tai.cost <")]).is_err());
+        assert!(bt(custom_data("BT", &[("c1", "true")]).unwrap()).is_err());
+        assert!(bt(custom_data("BT", &[("tai", "tai.price < 5")]).unwrap()).is_err());
+        assert!(bt(custom_data("BT", &[("tai", "ms[10] > 0")]).unwrap()).is_err());
+        // Errors that only appear during the sim fail it too
+        let err = bt(custom_data("BT", &[("tai", "t < 60 || -\"x\" < 0")]).unwrap()).unwrap_err();
+        assert!(matches!(err, SimError::InvalidQuery { message } if message.starts_with("Error in the condition of tai")));
+    }
+}
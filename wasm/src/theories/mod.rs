@@ -0,0 +1,98 @@
+/*!
+ * Theory sims
+ */
+
+pub mod theory;
+pub mod custom_strat;
+pub mod multiplier;
+pub mod cts;
+pub mod unofficial_cts;
+
+use crate::api::{config::TheoryType, error::SimError, query::PubTableQuery, response::PubTableResponse};
+use crate::utils::result::SimResult;
+use theory::{TheoryBase, TheoryData};
+
+/** Runs the sim of the theory given in `data` */
+pub fn simulate_theory(data: TheoryData) -> Result<SimResult, SimError> {
+    if is_unofficial(data.theory) && !data.settings.show_unofficials {
+        return Err(SimError::invalid(format!("{:?} is an unofficial theory, enable unofficial theories to simulate it", data.theory)));
+    }
+    let res = match data.theory {
+        TheoryType::MF => cts::mf::mf(data),
+        TheoryType::BaP => cts::bap::bap(data),
+        TheoryType::BT => unofficial_cts::bt::bt(data),
+        TheoryType::TC => unofficial_cts::tc::tc(data),
+        theory => Err(SimError::invalid(format!("Theory {theory:?} is not supported by the sim yet")))
+    };
+    res.and_then(check_result)
+}
+
+/** Rejects results holding values that cannot be represented */
+fn check_result(res: SimResult) -> Result<SimResult, SimError> {
+    if res.tau_h.is_nan() || res.time.is_nan() || res.pub_rho.log10_f64().is_nan() {
+        return Err(SimError::numeric(format!("The sim of {:?} ({}) gave an invalid result", res.theory, res.strat)));
+    }
+    Ok(res)
+}
+
+/**
+ * Returns the initial state of the sim of the theory given in `data`, None if the theory is not supported
+ *
+ * Unofficial theories are described even if they are hidden.
+ */
+pub fn theory_base(data: &TheoryData) -> Result<Option<TheoryBase>, SimError> {
+    match data.theory {
+        TheoryType::MF => cts::mf::base(data).map(Some),
+        TheoryType::BaP => cts::bap::base(data).map(Some),
+        TheoryType::BT => unofficial_cts::bt::base(data).map(Some),
+        TheoryType::TC => unofficial_cts::tc::base(data).map(Some),
+        _ => Ok(None)
+    }
+}
+
+/** Regenerates the publication table of a theory with forced publications */
+pub fn generate_pub_table(query: PubTableQuery) -> Result<PubTableResponse, SimError> {
+    match query.theory {
+        TheoryType::BaP => cts::bap::generate_pub_table(query),
+        theory => Err(SimError::invalid(format!("Publication table generation is not supported for {theory:?} yet")))
+    }
+}
+
+/** Returns true if the sim of `theory` is ported */
+pub fn is_supported(theory: TheoryType) -> bool {
+    matches!(theory, TheoryType::MF | TheoryType::BaP | TheoryType::BT | TheoryType::TC)
+}
+
+/** Returns true if `theory` is an unofficial custom theory */
+pub fn is_unofficial(theory: TheoryType) -> bool {
+    matches!(theory, TheoryType::BT | TheoryType::TC)
+}
+
+/** Creates the data of a sim with default settings */
+#[cfg(test)]
+pub fn test_data(theory: TheoryType, strat: &str, rho: f64) -> TheoryData {
+    use crate::utils::{lognum::LogNum, settings::{CompletedCTs, SimAllStrats, SimSettings}};
+
+    TheoryData {
+        theory,
+        sigma: 0,
+        rho: LogNum::from_log10(rho),
+        strat: strat.to_owned(),
+        cap: None,
+        time_cap: None,
+        check_ins: None,
+        autobuy: false,
+        resume: None,
+        custom_strat: None,
+        settings: SimSettings {
+            dt: 1.5,
+            ddt: 1.0001,
+            mf_reset_depth: 0,
+            bought_vars_delta: 5,
+            sim_all_strats: SimAllStrats::All,
+            completed_cts: CompletedCTs::No,
+            show_a23: false,
+            show_unofficials: false
+        }
+    }
+}
@@ -0,0 +1,114 @@
+/*!
+ * Publication multiplier model of each theory
+ *
+ * The total multiplier (log10) of a theory is `max(0, slope * x + offset)`, where `x` is the
+ * rho (log10) of the last publication, or its tau for theories scaled by their tau factor.
+ * Main theories add the R9 multiplier given by the students on top.
+ */
+
+use crate::api::config::TheoryType;
+
+/** Multiplier formula of a theory */
+#[derive(Debug, Clone, Copy)]
+pub struct MultiplierModel {
+    pub slope: f64,
+    pub offset: f64,
+    /** The slope applies to tau instead of rho */
+    pub per_tau: bool,
+    /** The R9 multiplier applies to the theory */
+    pub r9: bool,
+    /** rho (log10) under which there is no multiplier, for theories whose formula is gated by the publication unlock */
+    pub unlock: Option<f64>
+}
+
+impl MultiplierModel {
+    const fn main(slope: f64, offset: f64) -> Self {
+        MultiplierModel { slope, offset, per_tau: false, r9: true, unlock: None }
+    }
+
+    const fn custom(slope: f64, offset: f64, per_tau: bool) -> Self {
+        MultiplierModel { slope, offset, per_tau, r9: false, unlock: None }
+    }
+
+    const fn gated(self, unlock: f64) -> Self {
+        MultiplierModel { unlock: Some(unlock), ..self }
+    }
+
+    /** Returns the multiplier formula of `theory` */
+    pub fn of(theory: TheoryType) -> Self {
+        match theory {
+            TheoryType::T1 => Self::main(0.164, -(3f64.log10())),
+            TheoryType::T2 => Self::main(0.198, -(100f64.log10())),
+            TheoryType::T3 => Self::main(0.147, 3f64.log10()),
+            TheoryType::T4 => Self::main(0.165, -(4f64.log10())),
+            TheoryType::T5 => Self::main(0.159, 0.),
+            TheoryType::T6 => Self::main(0.196, -(50f64.log10())),
+            TheoryType::T7 => Self::main(0.152, 0.),
+            TheoryType::T8 => Self::main(0.15, 0.),
+            TheoryType::WSP | TheoryType::SL => Self::custom(0.375, 0., true),
+            TheoryType::EF => Self::custom(0.09675, 0., true),
+            TheoryType::CSR2 => Self::custom(0.55075, -(200f64.log10()), true),
+            TheoryType::FI => Self::custom(0.1625, 0., true),
+            TheoryType::FP => Self::custom(0.331, 5f64.log10(), true).gated(12.),
+            TheoryType::RZ => Self::custom(0.2102, 2f64.log10(), false),
+            TheoryType::MF => Self::custom(0.17, 0., true).gated(8.),
+            TheoryType::BaP => Self::custom(0.132075, 5f64.log10(), true).gated(7.),
+            TheoryType::BT => Self::custom(1.25, 0., true),
+            TheoryType::TC => Self::custom(0.2, -(2f64.log10()), false)
+        }
+    }
+
+    /** Returns the formula applied to `rho` (log10), ignoring the unlock and the R9 multiplier */
+    pub fn theory_multiplier(&self, rho: f64, tau_factor: f64) -> f64 {
+        let x = if self.per_tau { rho * tau_factor } else { rho };
+        (x * self.slope + self.offset).max(0.)
+    }
+
+    /** Returns the total multiplier (log10) of a theory whose last publication is `rho` (log10) */
+    pub fn multiplier(&self, rho: f64, tau_factor: f64, sigma: i64) -> f64 {
+        if self.unlock.is_some_and(|unlock| rho < unlock) {
+            return 0.;
+        }
+        self.theory_multiplier(rho, tau_factor) + if self.r9 { r9_multiplier(sigma) } else { 0. }
+    }
+
+    /** Returns the rho (log10) of the last publication giving the total multiplier `multiplier` (log10) */
+    pub fn rho(&self, multiplier: f64, tau_factor: f64, sigma: i64) -> f64 {
+        let r9 = if self.r9 { r9_multiplier(sigma) } else { 0. };
+        let x = (multiplier - r9 - self.offset) / self.slope;
+        if self.per_tau { x / tau_factor } else { x }
+    }
+}
+
+/** Returns the R9 multiplier (log10) given by `sigma` students */
+pub fn r9_multiplier(sigma: i64) -> f64 {
+    let exponent = if sigma < 65 { 0 } else if sigma < 75 { 1 } else if sigma < 85 { 2 } else { 3 };
+    (sigma as f64 / 20.).powi(exponent).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_formulas() {
+        for i in 0..=TheoryType::TC as usize {
+            let theory = TheoryType::try_from(i).unwrap();
+            let model = MultiplierModel::of(theory);
+            for (rho, sigma) in [(100., 0), (300., 70), (1000., 100)] {
+                let multiplier = model.multiplier(rho, 0.4, sigma);
+                assert!((model.rho(multiplier, 0.4, sigma) - rho).abs() < 1e-9, "{theory:?} at {rho}");
+            }
+        }
+    }
+
+    #[test]
+    fn main_theory_formulas() {
+        let model = MultiplierModel::of(TheoryType::T1);
+        assert!((model.multiplier(100., 1., 100) - (16.4 - 3f64.log10() + 125f64.log10())).abs() < 1e-9);
+        assert_eq!(model.multiplier(1., 1., 0), 0.);
+        assert_eq!(r9_multiplier(64), 0.);
+        assert!((r9_multiplier(80) - 4f64.powi(2).log10()).abs() < 1e-12);
+        assert_eq!(MultiplierModel::of(TheoryType::MF).multiplier(7., 1., 0), 0.);
+    }
+}
@@ -0,0 +1,584 @@
+/*!
+ * Base structure and trait shared by all theories
+ */
+
+use std::sync::{Arc, OnceLock};
+
+use num::Float;
+
+use crate::api::{config::{TheoryType, current_config}, error::SimError, query::ResumeState};
+use crate::theories::{
+    custom_strat::CustomStrat,
+    multiplier::{MultiplierModel, r9_multiplier}
+};
+use crate::utils::{
+    binary_insertion_search,
+    currency::Currency,
+    expr::normalize_ident,
+    lognum::{self, LogNum},
+    result::SimResult,
+    schedule::CheckInSchedule,
+    settings::SimSettings,
+    var_buy::VarBuy,
+    variable::Variable
+};
+
+/** Data needed to create a theory sim */
+#[derive(Debug, Clone)]
+pub struct TheoryData {
+    pub theory: TheoryType,
+    pub sigma: i64,
+    pub rho: LogNum,
+    pub strat: String,
+    pub cap: Option<LogNum>,
+    /** Time (seconds) at which the publication is forced */
+    pub time_cap: Option<f64>,
+    /** Publications (and purchases if set) only happen at check-ins */
+    pub check_ins: Option<CheckInSchedule>,
+    /** Every available variable is bought, ignoring the buying conditions */
+    pub autobuy: bool,
+    /** In-game state from which the publication is continued */
+    pub resume: Option<ResumeState>,
+    /** User-defined buying conditions overriding the ones of `strat` */
+    pub custom_strat: Option<Arc<CustomStrat>>,
+    pub settings: SimSettings
+}
+
+/** Holds the state shared by all theories */
+#[derive(Debug, Clone)]
+pub struct TheoryBase {
+    pub theory: TheoryType,
+    /** Current strategy */
+    pub strat: String,
+    /** User-defined buying conditions overriding the ones of `strat` */
+    pub custom_strat: Option<Arc<CustomStrat>>,
+    /** First error in the conditions of the custom strategy, shared with the forks of the sim */
+    pub custom_strat_error: Arc<OnceLock<SimError>>,
+    /** Every available variable is bought, ignoring the buying conditions */
+    pub autobuy: bool,
+    /** tau/rho conversion rate */
+    pub tau_factor: f64,
+    /** Sim settings used in the simulation */
+    pub settings: SimSettings,
+
+    // Theory
+    /** rho (log10) at which publications are unlocked */
+    pub pub_unlock: f64,
+    /** cap at which simulation will stop */
+    pub cap: LogNum,
+    /** time at which the simulation will stop */
+    pub time_cap: f64,
+    /** rho of the last publication */
+    pub last_pub: LogNum,
+    /** number of students */
+    pub sigma: i64,
+    /** current total multiplier */
+    pub tot_mult: LogNum,
+    /** current publication multiplier increase for the next pub */
+    pub cur_mult: f64,
+    /** tick length */
+    pub dt: f64,
+    /** tick growth speed */
+    pub ddt: f64,
+    /** real elapsed time of the publication */
+    pub t: f64,
+    /** number of elapsed ticks */
+    pub ticks: u64,
+    /** Check-in schedule of the player, if any */
+    pub check_ins: Option<CheckInSchedule>,
+    /** time of the next check-in */
+    pub next_check_in: f64,
+    /** true if the player checked in during the last tick, always true without check-in schedule */
+    pub at_check_in: bool,
+    /** previous milestone count */
+    pub prev_milestone_count: i32,
+
+    // Currencies
+    /** Main currency of the theory */
+    pub rho: Currency,
+    /** max value of rho for this publication */
+    pub max_rho: LogNum,
+
+    // Variables
+    pub variables: Vec<Variable>,
+    /** List of recorded variable purchases */
+    pub bought_vars: Vec<VarBuy>,
+
+    // Publication values
+    /** Average tau/hr gain at this point in the publication (can be negative) */
+    pub tau_h: f64,
+    /** Maximum tau/hr gain in the publication (can be negative) */
+    pub max_tau_h: f64,
+    /** final publication time */
+    pub pub_t: f64,
+    /** final rho of the publication */
+    pub pub_rho: LogNum,
+
+    // Milestones
+    /** Level of each milestone */
+    pub milestones: Vec<i32>,
+    /** Maximum level for each milestone */
+    pub milestones_max: Vec<i32>,
+    /** Milestone unlock points (log10 of rho), overwritten by `milestone_unlock_steps` if set */
+    pub milestone_unlocks: Vec<f64>,
+    /** Steps of rho (log10) at which milestones are unlocked, takes priority over `milestone_unlocks` */
+    pub milestone_unlock_steps: f64,
+    /** Milestone allocation chosen by the player, used until a new milestone is unlocked */
+    pub milestone_allocation: Option<Vec<i32>>
+}
+
+impl TheoryBase {
+    /** Creates the base state of a theory from the sim data */
+    pub fn new(data: &TheoryData) -> Result<Self, SimError> {
+        let tau_factor = current_config()?
+            .theories.get(&data.theory)
+            .ok_or_else(|| SimError::no_theory_config(data.theory))?
+            .tau_factor;
+
+        Ok(TheoryBase {
+            theory: data.theory,
+            strat: data.strat.clone(),
+            custom_strat: data.custom_strat.clone(),
+            custom_strat_error: Arc::new(OnceLock::new()),
+            autobuy: data.autobuy,
+            tau_factor,
+            settings: data.settings.clone(),
+            pub_unlock: 1.,
+            cap: match data.cap {
+                Some(cap) if cap.log10_f64() > 0. => cap,
+                _ => LogNum::infinity()
+            },
+            time_cap: data.time_cap.unwrap_or(f64::INFINITY),
+            last_pub: data.rho,
+            sigma: data.sigma,
+            tot_mult: lognum::ONE,
+            cur_mult: 0.,
+            dt: data.settings.dt,
+            ddt: data.settings.ddt,
+            t: 0.,
+            ticks: 0,
+            next_check_in: data.check_ins.as_ref().map_or(0., |check_ins| check_ins.next_check_in(0.)),
+            check_ins: data.check_ins.clone(),
+            at_check_in: data.check_ins.is_none(),
+            prev_milestone_count: -1,
+            rho: Currency::new_rho(),
+            max_rho: lognum::ZERO,
+            variables: Vec::new(),
+            bought_vars: Vec::new(),
+            tau_h: 0.,
+            max_tau_h: 0.,
+            pub_t: 0.,
+            pub_rho: lognum::ZERO,
+            milestones: Vec::new(),
+            milestones_max: Vec::new(),
+            milestone_unlocks: Vec::new(),
+            milestone_unlock_steps: -1.,
+            milestone_allocation: None
+        })
+    }
+
+    /** Returns the total multiplier of a publication at `rho`, ignoring the publication unlock */
+    pub fn multiplier_at(&self, rho: LogNum) -> LogNum {
+        let model = MultiplierModel::of(self.theory);
+        let r9 = if model.r9 { r9_multiplier(self.sigma) } else { 0. };
+        LogNum::from_log10(model.theory_multiplier(rho.log10_f64(), self.tau_factor) + r9)
+    }
+
+    /** Returns the number of unlocked milestones */
+    fn milestone_count(&self) -> i32 {
+        let rho = self.max_rho.max(self.last_pub).log10_f64();
+        if self.milestone_unlock_steps > 0. {
+            (rho / self.milestone_unlock_steps).floor() as i32
+        } else {
+            binary_insertion_search(&self.milestone_unlocks, rho)
+        }
+    }
+
+    /** Distributes `milestone_count` milestones following `priority` */
+    fn distribute_milestones(&mut self, priority: &[usize], mut milestone_count: i32) {
+        if let Some(allocation) = &self.milestone_allocation {
+            if allocation.iter().sum::<i32>() == milestone_count {
+                self.milestones.clone_from(allocation);
+                return;
+            }
+            self.milestone_allocation = None;
+        }
+        self.milestones.clear();
+        self.milestones.resize(self.milestones_max.len(), 0);
+        for &i in priority {
+            while self.milestones[i] < self.milestones_max[i] && milestone_count > 0 {
+                self.milestones[i] += 1;
+                milestone_count -= 1;
+            }
+        }
+    }
+
+    /** Updates milestones following `priority` */
+    pub fn update_milestones(&mut self, priority: &[usize]) {
+        let milestone_count = self.milestone_count();
+        self.distribute_milestones(priority, milestone_count);
+    }
+
+    /**
+     * Updates milestones following `priority`, only if the milestone count changed
+     *
+     * Returns true if milestones were updated
+     */
+    pub fn update_milestones_no_ms(&mut self, priority: &[usize]) -> bool {
+        let milestone_count = self.milestone_count();
+        if milestone_count == self.prev_milestone_count {
+            return false;
+        }
+        self.prev_milestone_count = milestone_count;
+        self.distribute_milestones(priority, milestone_count);
+        true
+    }
+
+    /**
+     * Sets the state shared by all theories to a live in-game state
+     *
+     * Must be called once the variables of the theory are created.
+     */
+    pub fn resume(&mut self, state: &ResumeState) -> Result<(), SimError> {
+        if !(state.t.is_finite() && state.t >= 0.) {
+            return Err(SimError::invalid("The time since the publication must be positive"));
+        }
+        for (name, &level) in &state.levels {
+            let var = self.variables.iter_mut()
+                .find(|var| normalize_ident(&var.name) == normalize_ident(name))
+                .ok_or_else(|| SimError::invalid(format!("Unknown variable {name} for {:?}", self.theory)))?;
+            if level < 0 {
+                return Err(SimError::invalid(format!("Invalid level {level} for {name}")));
+            }
+            var.set(level);
+        }
+        if let Some(milestones) = &state.milestones {
+            if milestones.len() != self.milestones_max.len() {
+                return Err(SimError::invalid(format!("{:?} has {} milestones", self.theory, self.milestones_max.len())));
+            }
+            self.milestone_allocation = Some(milestones.clone());
+        }
+        self.prev_milestone_count = -1;
+        self.rho.value = state.rho;
+        self.max_rho = state.max_rho.unwrap_or(state.rho).max(state.rho);
+        self.t = state.t;
+        Ok(())
+    }
+
+    /** Checks the custom strategy against the variables of the theory, if there is one */
+    pub fn validate_custom_strat(&self) -> Result<(), SimError> {
+        match &self.custom_strat {
+            Some(custom_strat) => custom_strat.validate(self),
+            None => Ok(())
+        }
+    }
+
+    /**
+     * Buying condition of the variable `id` given by the custom strategy, None if it has none
+     *
+     * A condition that fails to evaluate is false, and its error is kept to fail the sim.
+     */
+    pub fn custom_buying_condition(&self, id: usize) -> Option<bool> {
+        match self.custom_strat.as_ref()?.buying_condition(self, id)? {
+            Ok(condition) => Some(condition),
+            Err(err) => {
+                let _ = self.custom_strat_error.set(err);
+                Some(false)
+            }
+        }
+    }
+
+    /** Returns `res`, or the error of the custom strategy if its conditions failed during the sim */
+    pub fn custom_strat_result(&self, res: SimResult) -> Result<SimResult, SimError> {
+        match self.custom_strat_error.get() {
+            Some(err) => Err(err.clone()),
+            None => Ok(res)
+        }
+    }
+
+    /** Default forced publication condition: publications must be unlocked */
+    pub fn evaluate_forced_pub_conditions(&self) -> bool {
+        self.pub_rho.log10_f64() >= self.pub_unlock
+    }
+
+    /** Default publication condition: the cap or the time cap is reached */
+    pub fn evaluate_pub_conditions(&self) -> bool {
+        self.max_rho >= self.cap || self.t >= self.time_cap
+    }
+
+    /** Default sim end condition: the publication time was doubled without finding a better point */
+    pub fn evaluate_sim_end_conditions(&self) -> bool {
+        self.t > self.pub_t * 2.
+    }
+
+    /** Updates `t` and `dt`, and whether the player checked in during the tick */
+    pub fn update_t(&mut self) {
+        self.t += self.dt / 1.5;
+        self.dt *= self.ddt;
+        if let Some(check_ins) = &self.check_ins {
+            self.at_check_in = self.t >= self.next_check_in;
+            if self.at_check_in {
+                self.next_check_in = check_ins.next_check_in(self.t);
+            }
+        }
+    }
+
+    /** Returns true if variables can be bought during this tick */
+    pub fn can_buy(&self) -> bool {
+        self.at_check_in || self.check_ins.as_ref().is_none_or(|check_ins| !check_ins.buy_at_check_ins)
+    }
+
+    /** Buys the variable `id` with rho and records the purchase if needed */
+    pub fn buy_variable(&mut self, id: usize) {
+        let var = &self.variables[id];
+        if self.max_rho.log10_f64() + self.settings.bought_vars_delta as f64 > self.last_pub.log10_f64() {
+            self.bought_vars.push(VarBuy {
+                var_name: var.name.clone(),
+                level: var.level + 1,
+                cost: var.cost,
+                symbol: self.rho.symbol.clone(),
+                timestamp: self.t
+            });
+        }
+        self.rho.subtract(var.cost);
+        self.variables[id].buy();
+    }
+
+    /** Removes the variable purchases that occurred after the publication point */
+    pub fn trim_bought_vars(&mut self) {
+        while self.bought_vars.last().is_some_and(|buy| buy.timestamp > self.pub_t) {
+            self.bought_vars.pop();
+        }
+    }
+}
+
+/** Trait implemented by every theory sim */
+pub trait Theory: Clone {
+    fn base(&self) -> &TheoryBase;
+    fn base_mut(&mut self) -> &mut TheoryBase;
+
+    /** Returns the total multiplier for a given rho value */
+    fn get_tot_mult(&self, rho: LogNum) -> LogNum;
+
+    /** Returns the order (0-indexed) in which milestones must be distributed */
+    fn get_milestone_priority(&self) -> &'static [usize];
+
+    /** Buying condition of the variable `id` for the current strategy */
+    fn buying_condition(&self, id: usize) -> bool;
+
+    /** Buying condition of the variable `id`, given by the custom strategy if it defines one */
+    fn strat_buying_condition(&self, id: usize) -> bool {
+        let base = self.base();
+        if base.autobuy {
+            return true;
+        }
+        base.custom_buying_condition(id)
+            .unwrap_or_else(|| self.buying_condition(id))
+    }
+
+    /** Availability of the variable `id` */
+    fn variable_availability(&self, id: usize) -> bool;
+
+    /** Extra buying condition if needed */
+    fn extra_buying_condition(&self, _id: usize) -> bool {
+        true
+    }
+
+    /** Runs each time a variable is purchased */
+    fn on_variable_purchased(&mut self, _id: usize) {}
+
+    /** Runs once per tick if a variable was bought */
+    fn on_any_variable_purchased(&mut self) {}
+
+    /** Sets a secondary currency when resuming a publication */
+    fn set_currency(&mut self, name: &str, _value: LogNum) -> Result<(), SimError> {
+        Err(SimError::invalid(format!("Unknown currency {name} for {:?}", self.base().theory)))
+    }
+
+    /** Recomputes the values depending on variable levels and milestones after resuming a publication */
+    fn on_resume(&mut self) {
+        self.update_milestones();
+    }
+
+    /** Continues the publication from a live in-game state */
+    fn resume(&mut self, state: &ResumeState) -> Result<(), SimError> {
+        self.base_mut().resume(state)?;
+        for (name, value) in &state.currencies {
+            self.set_currency(name, *value)?;
+        }
+        self.on_resume();
+        Ok(())
+    }
+
+    /**
+     * Returns the weights of the costs when using `buy_variables_weight`
+     *
+     * Weights multiply the costs, an infinite weight prevents the purchase.
+     */
+    fn get_variable_weights(&self) -> Vec<LogNum> {
+        vec![lognum::ONE; self.base().variables.len()]
+    }
+
+    /** Advances the theory by one tick */
+    fn tick(&mut self);
+
+    /** Runs the simulation and returns its result */
+    fn simulate(&mut self) -> SimResult;
+
+    /** Prevents the sim from publishing if one of these conditions is not satisfied */
+    fn evaluate_forced_pub_conditions(&self) -> bool {
+        self.base().evaluate_forced_pub_conditions()
+    }
+
+    /** If one of these conditions is reached, the publication ends at that point */
+    fn evaluate_pub_conditions(&self) -> bool {
+        self.base().evaluate_pub_conditions()
+    }
+
+    /**
+     * If one of these conditions is reached, the simulation ends
+     * and the publication point is set at the last peak of tau/hr
+     */
+    fn evaluate_sim_end_conditions(&self) -> bool {
+        self.base().evaluate_sim_end_conditions()
+    }
+
+    /** Determines if `evaluate_sim_end_conditions` is checked */
+    fn do_sim_end_conditions(&self) -> bool {
+        true
+    }
+
+    /**
+     * Returns true if the simulation loop should end
+     *
+     * With a check-in schedule, the simulation can only end at check-ins. A failing custom
+     * strategy ends it immediately.
+     */
+    fn end_simulation(&self) -> bool {
+        if self.base().custom_strat_error.get().is_some() {
+            return true;
+        }
+        self.base().at_check_in
+            && self.evaluate_forced_pub_conditions()
+            && (self.evaluate_pub_conditions()
+                || (self.do_sim_end_conditions() && self.evaluate_sim_end_conditions()))
+    }
+
+    /** Updates milestones */
+    fn update_milestones(&mut self) {
+        let priority = self.get_milestone_priority();
+        self.base_mut().update_milestones(priority);
+    }
+
+    /** Updates milestones only if the milestone count changed, returns true if they were updated */
+    fn update_milestones_no_ms(&mut self) -> bool {
+        let priority = self.get_milestone_priority();
+        self.base_mut().update_milestones_no_ms(priority)
+    }
+
+    /** Updates several sim status parameters */
+    fn update_sim_status(&mut self) {
+        let base = self.base_mut();
+        if base.rho.value > base.max_rho {
+            base.max_rho = base.rho.value;
+        }
+        base.update_t();
+        base.tau_h = base.tau_factor * (base.max_rho.log10_f64() - base.last_pub.log10_f64()) / (base.t / 3600.);
+
+        // With a check-in schedule, the publication can only happen at check-ins
+        let update_pub = !self.evaluate_forced_pub_conditions()
+            || (self.base().at_check_in
+                && (self.base().max_tau_h < self.base().tau_h || self.evaluate_pub_conditions()));
+        let cur_tot_mult = self.get_tot_mult(self.base().max_rho);
+
+        let base = self.base_mut();
+        if update_pub {
+            base.max_tau_h = base.tau_h;
+            base.pub_t = base.t;
+            base.pub_rho = base.max_rho;
+        }
+        base.cur_mult = 10f64.powf(cur_tot_mult.log10_f64() - base.tot_mult.log10_f64());
+        base.ticks += 1;
+    }
+
+    /**
+     * Buys variables with rho
+     *
+     * Variables are bought from the end of the variable list.
+     */
+    fn buy_variables(&mut self) {
+        if !self.base().can_buy() {
+            return;
+        }
+        let mut bought = false;
+        for i in (0..self.base().variables.len()).rev() {
+            while self.base().rho.value > self.base().variables[i].cost
+                && self.strat_buying_condition(i)
+                && self.variable_availability(i)
+                && self.extra_buying_condition(i)
+            {
+                self.base_mut().buy_variable(i);
+                bought = true;
+                self.on_variable_purchased(i);
+            }
+        }
+        if bought {
+            self.on_any_variable_purchased();
+        }
+    }
+
+    /**
+     * Buys variables using a weighted cost algorithm
+     *
+     * The weight of the cost of each variable is defined by `get_variable_weights`.
+     * Variables whose custom buying condition is false are not bought.
+     */
+    fn buy_variables_weight(&mut self) {
+        if !self.base().can_buy() {
+            return;
+        }
+        let mut bought = false;
+        loop {
+            let weights = self.get_variable_weights();
+            let mut min_cost: Option<(LogNum, usize)> = None;
+            for i in (0..self.base().variables.len()).rev() {
+                let weighted_cost = self.base().variables[i].cost * weights[i];
+                let custom_condition = self.base().autobuy || self.base().custom_buying_condition(i).unwrap_or(true);
+                if min_cost.is_none_or(|(cost, _)| weighted_cost < cost) && self.variable_availability(i) && custom_condition {
+                    min_cost = Some((weighted_cost, i));
+                }
+            }
+            match min_cost {
+                Some((cost, i)) if cost < LogNum::infinity() && self.base().variables[i].cost < self.base().rho.value => {
+                    self.base_mut().buy_variable(i);
+                    bought = true;
+                    self.on_variable_purchased(i);
+                },
+                _ => break
+            }
+        }
+        if bought {
+            self.on_any_variable_purchased();
+        }
+    }
+
+    /** Creates a sim result from the current state, `strat_extra` is appended to the strategy name */
+    fn create_result(&self, strat_extra: &str) -> SimResult {
+        let base = self.base();
+        let custom_name = base.custom_strat.as_ref()
+            .map_or(String::new(), |custom_strat| format!(" custom: {}", custom_strat.name));
+        SimResult {
+            theory: base.theory,
+            sigma: base.sigma,
+            last_pub: base.last_pub,
+            pub_rho: base.pub_rho,
+            delta_tau: (base.pub_rho / base.last_pub).powf64(base.tau_factor),
+            pub_multi: 10f64.powf(self.get_tot_mult(base.pub_rho).log10_f64() - base.tot_mult.log10_f64()),
+            strat: format!("{}{}{}{}", base.strat, strat_extra, custom_name, if base.autobuy { " autobuy" } else { "" }),
+            tau_h: base.max_tau_h,
+            time: base.pub_t.max(0.),
+            ticks: base.ticks,
+            bought_vars: base.bought_vars.clone()
+        }
+    }
+}
@@ -0,0 +1,157 @@
+/*!
+ * BT
+ */
+
+use num::Float;
+
+use crate::api::{config::TheoryType, error::SimError};
+use crate::theories::theory::{Theory, TheoryBase, TheoryData};
+use crate::utils::{
+    cost::{Cost, ExponentialCost, FirstFreeCost},
+    lognum::LogNum,
+    result::SimResult,
+    value::Value,
+    variable::Variable
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BtStrat {
+    Idle,
+    D
+}
+
+impl BtStrat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "BT" => Some(Self::Idle),
+            "BTd" => Some(Self::D),
+            _ => None
+        }
+    }
+}
+
+/** Runs the BT sim */
+pub fn bt(data: TheoryData) -> Result<SimResult, SimError> {
+    let mut sim = BtSim::new(&data)?;
+    let res = sim.simulate();
+    sim.base.custom_strat_result(res)
+}
+
+/** Returns the initial state of a BT sim */
+pub fn base(data: &TheoryData) -> Result<TheoryBase, SimError> {
+    Ok(BtSim::new(data)?.base)
+}
+
+#[derive(Debug, Clone)]
+struct BtSim {
+    base: TheoryBase,
+    strat: BtStrat
+}
+
+impl BtSim {
+    fn new(data: &TheoryData) -> Result<Self, SimError> {
+        let strat = BtStrat::from_name(&data.strat)
+            .ok_or_else(|| SimError::unknown_strategy(TheoryType::BT, &data.strat))?;
+
+        let mut base = TheoryBase::new(data)?;
+        base.pub_unlock = 7.;
+        base.milestone_unlocks = vec![20., 40., 60., 100., 150., 250., 750., 850., 950., 1050., 1150., 1250., 1450.];
+        base.milestones_max = vec![3, 3, 6, 1];
+        base.variables = vec![
+            Variable::new("tai", Cost::new(FirstFreeCost::new(ExponentialCost::new(15., 2.))), Value::new_stepwise(2., 10, 0.)),
+            Variable::new("rao", Cost::new_exponential(5., 10.), Value::new_exponential(2.)),
+            Variable::new("tay", Cost::Exponential(ExponentialCost::new_log2(1e10, 10.)), Value::new_exponential(10.))
+        ];
+
+        let mut sim = BtSim { base, strat };
+        if data.rho.log10_f64() >= sim.base.pub_unlock {
+            sim.base.tot_mult = sim.get_tot_mult(data.rho);
+        }
+        sim.update_milestones();
+        sim.base.validate_custom_strat()?;
+        if let Some(state) = &data.resume {
+            sim.resume(state)?;
+        }
+
+        Ok(sim)
+    }
+}
+
+impl Theory for BtSim {
+    fn base(&self) -> &TheoryBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut TheoryBase {
+        &mut self.base
+    }
+
+    fn get_tot_mult(&self, rho: LogNum) -> LogNum {
+        self.base.multiplier_at(rho)
+    }
+
+    fn get_milestone_priority(&self) -> &'static [usize] {
+        &[1, 0, 2, 3]
+    }
+
+    fn buying_condition(&self, id: usize) -> bool {
+        let vars = &self.base.variables;
+        match (self.strat, id) {
+            (BtStrat::D, 0) => {
+                let offset = if self.base.last_pub.log10_f64() < 275. { 12 } else { 10 };
+                vars[0].cost * LogNum::from(offset + vars[0].level % 10) < vars[1].cost
+            },
+            _ => true
+        }
+    }
+
+    fn variable_availability(&self, id: usize) -> bool {
+        match id {
+            2 => self.base.milestones[2] > 0,
+            _ => true
+        }
+    }
+
+    fn tick(&mut self) {
+        let milestones = &self.base.milestones;
+        let vars = &self.base.variables;
+        let tay_exponent = ((milestones[2] + 1) * (milestones[2] + 2)) as f64 * 0.5 - 1.;
+        let vtai = vars[0].value.powf64(1. + 0.08 * milestones[0] as f64);
+        let vrao = vars[1].value.powf64(1. + 0.077 * milestones[1] as f64);
+        let vtay = vars[2].value.powf64(if milestones[3] == 0 { tay_exponent * 0.0003 } else { 0.015 });
+        let rhodot = self.base.tot_mult * vtai * vrao * vtay;
+
+        self.base.rho.add(rhodot * LogNum::from(self.base.dt));
+        if milestones[3] == 1
+            && self.base.max_rho.max(self.base.last_pub).log10_f64() * self.base.tau_factor < 9f64.log10() + 599.
+        {
+            self.base.rho.value = LogNum::from_log10(1.05f64.log10() + 1500.);
+        }
+    }
+
+    fn simulate(&mut self) -> SimResult {
+        while !self.end_simulation() {
+            self.tick();
+            self.update_sim_status();
+            self.update_milestones();
+            self.buy_variables();
+        }
+        self.base.trim_bought_vars();
+        self.create_result("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::test_data;
+
+    #[test]
+    fn simulates_strats() {
+        for strat in ["BT", "BTd"] {
+            let res = bt(test_data(TheoryType::BT, strat, 200.)).unwrap();
+            assert!(res.pub_rho.log10_f64() > 200.);
+        }
+        assert!(bt(test_data(TheoryType::BT, "BTx", 200.)).is_err());
+    }
+}
@@ -0,0 +1,4 @@
+/*! Unofficial custom theories */
+
+pub mod tc;
+pub mod bt;
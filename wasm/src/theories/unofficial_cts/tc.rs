@@ -0,0 +1,327 @@
+/*!
+ * Temperature Control (TC)
+ */
+
+use std::f64::consts::{E, LOG10_E};
+
+use crate::api::{config::TheoryType, error::SimError};
+use crate::theories::theory::{Theory, TheoryBase, TheoryData};
+use crate::utils::{
+    lognum::{self, LogNum},
+    result::SimResult,
+    value::Value,
+    variable::Variable,
+    cost::Cost
+};
+
+/** Max heat duty in W */
+const Q: f64 = 20.;
+/** Thermal passive convection coefficient for Al (W/m^2 k) */
+const H: f64 = 5.;
+/** Heat capacity for Al (J/g/k) */
+const CP: f64 = 0.89;
+/** Area of the element (m^2) */
+const AREA: f64 = 0.024;
+/** Mass of the element (g) */
+const MASS: f64 = 10.;
+/** Ambient temperature */
+const TC: f64 = 30.;
+/** Time step of the thermal system */
+const SYSTEM_DT: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TcStrat {
+    Idle,
+    D
+}
+
+impl TcStrat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "TC" => Some(Self::Idle),
+            "TCd" => Some(Self::D),
+            _ => None
+        }
+    }
+
+    /** Returns kp, ki, kd and the set point of the PID controller */
+    fn pid_values(self) -> (f64, f64, f64, f64) {
+        match self {
+            Self::Idle | Self::D => (5., 30., 11., 189.)
+        }
+    }
+
+    /** Returns the amplitude and the frequency of the automation */
+    fn automation_settings(self) -> (f64, f64) {
+        match self {
+            Self::Idle | Self::D => (200., 1.)
+        }
+    }
+}
+
+/** Runs the TC sim */
+pub fn tc(data: TheoryData) -> Result<SimResult, SimError> {
+    let mut sim = TcSim::new(&data)?;
+    let res = sim.simulate();
+    sim.base.custom_strat_result(res)
+}
+
+/** Returns the initial state of a TC sim */
+pub fn base(data: &TheoryData) -> Result<TheoryBase, SimError> {
+    Ok(TcSim::new(data)?.base)
+}
+
+#[derive(Debug, Clone)]
+struct TcSim {
+    base: TheoryBase,
+    strat: TcStrat,
+    // Growing variables
+    r: LogNum,
+    p: LogNum,
+
+    achievement_multi: f64,
+
+    // System parameters
+    error: [f64; 2],
+    timer: f64,
+    integral: f64,
+    amplitude: f64,
+    frequency: f64,
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    temp: f64,
+    set_point: f64
+}
+
+impl TcSim {
+    fn new(data: &TheoryData) -> Result<Self, SimError> {
+        let strat = TcStrat::from_name(&data.strat)
+            .ok_or_else(|| SimError::unknown_strategy(TheoryType::TC, &data.strat))?;
+        let (kp, ki, kd, set_point) = strat.pid_values();
+        let (amplitude, frequency) = strat.automation_settings();
+        let last_pub = data.rho.log10_f64();
+
+        let mut base = TheoryBase::new(data)?;
+        base.pub_unlock = 8.;
+        base.variables = vec![
+            Variable::new("c1", Cost::new_exponential(1e5, 18.), Value::new_exponential(2.75)),
+            Variable::new("r1", Cost::new_exponential(10., 1.585), Value::new_stepwise(2., 10, 0.)),
+            Variable::new("r2", Cost::new_exponential(1000., 8.), Value::new_exponential(2.)),
+            Variable::new("c2", Cost::new_exponential(LogNum::from_log10(400.), 10f64.powf(4.5)), Value::new_exponential(E)),
+            Variable::new("dTexp", Cost::new_exponential(1e15, 1000.), Value::new_linear(1., 0.)),
+            Variable::new("p1", Cost::new_exponential(LogNum::from_log10(750.), 16.61), Value::new_stepwise(2., 10, 0.)),
+            Variable::new("p2", Cost::new_exponential(LogNum::from_log10(900.), 1e15), Value::new_exponential(2.)),
+            Variable::new("c1exp", Cost::new_exponential(1e30, 1e30), Value::new_linear(0.05, 1.)),
+            Variable::new("r1exp", Cost::new_exponential(1e40, 1e40), Value::new_linear(0.05, 1.)),
+            Variable::new("r2exp", Cost::new_exponential(1e150, 1e175), Value::new_linear(0.03, 1.)),
+            Variable::new("c1base", Cost::new_exponential(1e200, 1e175), Value::new_linear(0.125, 2.75))
+        ];
+        base.milestone_unlocks = vec![10., 50., 100., 400., 420., 440., 950., 1150.];
+        base.milestones_max = vec![1, 1, 1, 2, 1, 2];
+
+        let mut sim = TcSim {
+            base,
+            strat,
+            r: lognum::ONE,
+            p: lognum::ONE,
+            achievement_multi: if last_pub >= 750. { 30. } else if last_pub >= 600. { 10. } else { 1. },
+            error: [0., 0.],
+            timer: 0.,
+            integral: 0.,
+            amplitude,
+            frequency,
+            kp,
+            ki,
+            kd,
+            temp: 30.,
+            set_point
+        };
+        sim.base.tot_mult = sim.get_tot_mult(data.rho);
+
+        // Permanent upgrades are bought up to the last publication
+        for i in 7..11 {
+            while sim.base.variables[i].cost <= data.rho && sim.variable_availability(i) {
+                sim.base.variables[i].buy();
+            }
+        }
+        sim.recompute_c1_base();
+        sim.update_milestones();
+        sim.base.validate_custom_strat()?;
+        if let Some(state) = &data.resume {
+            sim.resume(state)?;
+        }
+
+        Ok(sim)
+    }
+
+    fn recompute_c1_base(&mut self) {
+        let c1_base = self.base.variables[10].value;
+        let c1 = &mut self.base.variables[0];
+        c1.value_model = Value::new_exponential(c1_base);
+        c1.compute_from_zero();
+    }
+}
+
+impl Theory for TcSim {
+    fn base(&self) -> &TheoryBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut TheoryBase {
+        &mut self.base
+    }
+
+    fn get_tot_mult(&self, rho: LogNum) -> LogNum {
+        self.base.multiplier_at(rho)
+    }
+
+    fn get_milestone_priority(&self) -> &'static [usize] {
+        &[0, 1, 2, 3, 4, 5]
+    }
+
+    fn buying_condition(&self, id: usize) -> bool {
+        let vars = &self.base.variables;
+        match (self.strat, id) {
+            (TcStrat::D, 1) => vars[1].cost * LogNum::from(10.) < vars[2].cost,
+            _ => true
+        }
+    }
+
+    fn variable_availability(&self, id: usize) -> bool {
+        let vars = &self.base.variables;
+        match id {
+            3 => vars[3].level <= 75,
+            4 => vars[4].level <= 100,
+            5 | 6 => self.achievement_multi == 30.,
+            7 | 8 => vars[id].level < 3,
+            9 | 10 => vars[id].level < 2,
+            _ => true
+        }
+    }
+
+    fn on_variable_purchased(&mut self, id: usize) {
+        if id == 10 {
+            self.recompute_c1_base();
+        }
+    }
+
+    fn set_currency(&mut self, name: &str, value: LogNum) -> Result<(), SimError> {
+        match name {
+            "r" => self.r = value,
+            "p" => self.p = value,
+            _ => return Err(SimError::invalid(format!("Unknown currency {name} for TC")))
+        }
+        Ok(())
+    }
+
+    fn on_resume(&mut self) {
+        self.recompute_c1_base();
+        self.update_milestones();
+    }
+
+    fn evaluate_forced_pub_conditions(&self) -> bool {
+        self.base.evaluate_forced_pub_conditions() && self.base.pub_rho >= self.base.last_pub
+    }
+
+    fn evaluate_sim_end_conditions(&self) -> bool {
+        self.base.evaluate_sim_end_conditions() || self.base.cur_mult > 15.
+    }
+
+    fn tick(&mut self) {
+        let milestones = &self.base.milestones;
+        let vki = if milestones[1] > 0 { self.ki } else { 0. };
+        let vkd = if milestones[2] > 0 { self.kd } else { 0. };
+        self.timer += SYSTEM_DT;
+
+        if self.timer > self.frequency {
+            self.temp = self.amplitude;
+            self.timer = 0.;
+            self.integral = 0.;
+        }
+
+        self.error[1] = self.error[0];
+        self.error[0] = self.set_point - self.temp;
+        self.integral += self.error[0];
+        let derivative = (self.error[0] - self.error[1]) / SYSTEM_DT;
+        // Anti-windup scheme
+        self.integral = self.integral.clamp(-100., 100.);
+        // Range 0-512
+        let output = (self.kp * self.error[0] + vki * self.integral + vkd * derivative)
+            .clamp(0., 512.)
+            .round();
+
+        // Heating simulation
+        let supplied_heat = Q * output / 512.;
+        let d_temp = (1. / MASS / CP * (supplied_heat - (self.temp - TC) * H * AREA)).abs();
+        let exponential_term = (supplied_heat - H * AREA * (self.temp - TC)) * E.powf(-SYSTEM_DT / MASS / CP);
+        self.temp = TC + (supplied_heat - exponential_term) / (H * AREA);
+
+        let vars = &self.base.variables;
+        let dt = LogNum::from(self.base.dt);
+
+        // P update
+        if self.achievement_multi == 30. {
+            let dp = vars[5].value
+                * vars[6].value
+                * LogNum::from_log10(LOG10_E * (-0.01 * 0.8f64.powi(milestones[4])))
+                * LogNum::from((self.temp - 100.).abs());
+            self.p += dp * dt;
+        }
+
+        // r update
+        let r1exp = vars[8].value.as_f64();
+        let r2exp = vars[9].value.as_f64();
+        let dr = vars[1].value.powf64(r1exp) * vars[2].value.powf64(r2exp)
+            / LogNum::from(1. + (1. + self.error[0].abs()).log10());
+        self.r += dr * dt;
+
+        // rho update
+        let c1exp = vars[7].value.as_f64();
+        let vc1 = vars[0].value.powf64(c1exp);
+        let vc2 = if milestones[4] > 0 { vars[3].value } else { lognum::ONE };
+        let d_temp_exp = 2. + vars[4].value.as_f64();
+        let mrexp = milestones[3] as f64;
+        let rhodot = self.p
+            * self.r.powf64(1. + mrexp * 0.001)
+            * (vc1 * vc2 * LogNum::from(d_temp).powf64(d_temp_exp)).powf64(0.5)
+            * self.base.tot_mult
+            * LogNum::from(self.achievement_multi);
+        self.base.rho.add(rhodot * dt);
+    }
+
+    fn simulate(&mut self) -> SimResult {
+        while !self.end_simulation() {
+            self.tick();
+            self.update_sim_status();
+            self.update_milestones();
+            self.buy_variables();
+        }
+        self.base.trim_bought_vars();
+        self.create_result("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theories::{simulate_theory, test_data};
+
+    #[test]
+    fn gated_by_show_unofficials() {
+        assert!(simulate_theory(test_data(TheoryType::TC, "TCd", 100.)).is_err());
+
+        let mut data = test_data(TheoryType::TC, "TCd", 100.);
+        data.settings.show_unofficials = true;
+        let res = simulate_theory(data).unwrap();
+        assert!(res.pub_rho.log10_f64() > 100.);
+    }
+
+    #[test]
+    fn permanent_upgrades() {
+        let sim = TcSim::new(&test_data(TheoryType::TC, "TC", 800.)).unwrap();
+        let levels: Vec<i32> = sim.base.variables[7..].iter().map(|var| var.level).collect();
+        assert_eq!(levels, [3, 3, 2, 2]);
+        assert_eq!(sim.achievement_multi, 30.);
+    }
+}
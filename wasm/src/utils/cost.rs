@@ -5,6 +5,8 @@
 use std::fmt::Debug;
 use num::Float;
 use dyn_clone::DynClone;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::utils::lognum::{self, LogNum};
 
@@ -25,9 +27,24 @@ pub trait CostTrait: DynClone + Debug {
     fn get_cost(&self, current_level: i32) -> LogNum{
         self.get_cost_to(current_level+1)
     }
+    /**
+     * Returns the model and its parameters
+     */
+    fn describe(&self) -> CostModel;
 }
 dyn_clone::clone_trait_object!(CostTrait);
 
+/** Description of a cost model and its parameters */
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CostModel {
+    Composite { model1: Box<CostModel>, model2: Box<CostModel>, cutoff: i32 },
+    Exponential { coefficient: LogNum, base: LogNum },
+    Stepwise { model: Box<CostModel>, step: i32 },
+    Constant { cost: LogNum },
+    FirstFree { model: Box<CostModel> }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CompositeCost<T: CostTrait, U: CostTrait> {
     pub model1: T,
@@ -66,6 +83,14 @@ impl<T: CostTrait + Clone, U: CostTrait + Clone> CostTrait for CompositeCost<T,
             self.model2.get_total_cost_to(level - self.cutoff) + self.model1.get_total_cost_to(self.cutoff)
         }
     }
+
+    fn describe(&self) -> CostModel {
+        CostModel::Composite {
+            model1: Box::new(self.model1.describe()),
+            model2: Box::new(self.model2.describe()),
+            cutoff: self.cutoff
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -100,6 +125,10 @@ impl CostTrait for ExponentialCost {
             self.coefficient * (self.base.powi(level)-lognum::ONE)/(self.base-lognum::ONE)
         }
     }
+
+    fn describe(&self) -> CostModel {
+        CostModel::Exponential { coefficient: self.coefficient, base: self.base }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -134,6 +163,10 @@ impl<T: CostTrait + Clone> CostTrait for StepwiseCost<T> {
             self.model.get_total_cost_to(int_part) * self.step.into() + self.model.get_cost_to(int_part + 1) * mod_part.into()
         }
     }
+
+    fn describe(&self) -> CostModel {
+        CostModel::Stepwise { model: Box::new(self.model.describe()), step: self.step }
+    }
 }
 
 
@@ -165,6 +198,10 @@ impl CostTrait for ConstantCost {
             self.cost*level.into()
         }
     }
+
+    fn describe(&self) -> CostModel {
+        CostModel::Constant { cost: self.cost }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -185,6 +222,10 @@ impl<T: CostTrait + Clone> CostTrait for FirstFreeCost<T> {
     fn get_total_cost_to(&self, level: i32) -> LogNum {
         self.model.get_total_cost_to(level - 1)
     }
+
+    fn describe(&self) -> CostModel {
+        CostModel::FirstFree { model: Box::new(self.model.describe()) }
+    }
 }
 
 /** enum holding different cost models */
@@ -231,4 +272,12 @@ impl Cost {
             Self::Other(cost) => cost.get_cost(current_level)
         }
     }
+
+    pub fn describe(&self) -> CostModel {
+        match self {
+            Self::Exponential(cost) => cost.describe(),
+            Self::Constant(cost) => cost.describe(),
+            Self::Other(cost) => cost.describe()
+        }
+    }
 }
\ No newline at end of file
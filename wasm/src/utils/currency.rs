@@ -7,8 +7,8 @@ use crate::utils::lognum::{self,LogNum};
 /** Struct representing a currency with a value and a symbol */
 #[derive(Debug, Clone)]
 pub struct Currency {
-    value: LogNum,
-    symbol: String
+    pub value: LogNum,
+    pub symbol: String
 }
 
 impl Currency {
@@ -0,0 +1,588 @@
+/*!
+ * Small expression language used for the conditions written in data.json
+ *
+ * Identifiers are case insensitive and may contain dots (`c1.cost`). A dash is always a minus,
+ * except inside the known dashed names of data.json (`Semi-Idle` is read as `semi_idle`), so
+ * `c1.cost-c2.cost` is a subtraction. Custom strategies also use indexing (`ms[1]`), function
+ * calls (`min(a, b)`) and the ternary operator.
+ */
+
+use std::fmt;
+
+/** Value produced by the evaluation of an expression */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprValue {
+    Number(f64),
+    Bool(bool),
+    Str(String)
+}
+
+impl ExprValue {
+    /** Truthiness of the value, following JS rules */
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Number(n) => *n != 0. && !n.is_nan(),
+            Self::Bool(b) => *b,
+            Self::Str(s) => !s.is_empty()
+        }
+    }
+
+    /** Numeric value of the value, following JS rules for booleans */
+    pub fn as_number(&self) -> Result<f64, String> {
+        match self {
+            Self::Number(n) => Ok(*n),
+            Self::Bool(b) => Ok(if *b { 1. } else { 0. }),
+            Self::Str(s) => Err(format!("Expected a number, found string \"{s}\""))
+        }
+    }
+}
+
+impl fmt::Display for ExprValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Str(s) => write!(f, "\"{s}\"")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Not,
+    Neg
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem
+}
+
+/** Functions that can be called in an expression */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Min,
+    Max,
+    Abs,
+    Floor,
+    Ceil,
+    Round,
+    Log10,
+    Ln,
+    Pow
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            "abs" => Some(Self::Abs),
+            "floor" => Some(Self::Floor),
+            "ceil" => Some(Self::Ceil),
+            "round" => Some(Self::Round),
+            "log10" => Some(Self::Log10),
+            "ln" | "log" => Some(Self::Ln),
+            "pow" => Some(Self::Pow),
+            _ => None
+        }
+    }
+
+    fn call(self, args: &[f64]) -> Result<f64, String> {
+        let unary = |f: fn(f64) -> f64| match args {
+            [x] => Ok(f(*x)),
+            _ => Err(format!("{self:?} expects 1 argument, found {}", args.len()))
+        };
+        match self {
+            Self::Min | Self::Max if args.is_empty() => Err(format!("{self:?} expects at least 1 argument")),
+            Self::Min => Ok(args.iter().copied().fold(f64::INFINITY, f64::min)),
+            Self::Max => Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            Self::Abs => unary(f64::abs),
+            Self::Floor => unary(f64::floor),
+            Self::Ceil => unary(f64::ceil),
+            Self::Round => unary(f64::round),
+            Self::Log10 => unary(f64::log10),
+            Self::Ln => unary(f64::ln),
+            Self::Pow => match args {
+                [x, y] => Ok(x.powf(*y)),
+                _ => Err(format!("Pow expects 2 arguments, found {}", args.len()))
+            }
+        }
+    }
+}
+
+/** Parsed expression */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Value(ExprValue),
+    Ident(String),
+    Index(String, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>)
+}
+
+/** Provides the values of the identifiers of an expression */
+pub trait ExprContext {
+    /** Returns the value of a (normalized) identifier, or None if it does not exist */
+    fn get(&self, name: &str) -> Option<ExprValue>;
+
+    /** Returns the value of `name[index]`, or None if it does not exist */
+    fn get_index(&self, _name: &str, _index: usize) -> Option<ExprValue> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma
+}
+
+/** Operators sorted so that longer operators are matched first */
+const OPERATORS: [&str; 19] = [
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||",
+    "<", ">", "!", "+", "-", "*", "/", "%", "?", ":", "="
+];
+
+/** Dashed names used by the conditions of data.json, the only identifiers containing a dash */
+const DASHED_NAMES: [&str; 2] = ["semi-idle", "very-active"];
+
+/** Normalizes an identifier the way data.json conditions expect it */
+pub fn normalize_ident(ident: &str) -> String {
+    ident.replace('-', "_").to_lowercase()
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') { j += 1; }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() { i += 1; }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f64>().map_err(|_| format!("Invalid number \"{text}\""))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            let is_ident_char = |c: &char| c.is_alphanumeric() || *c == '_';
+            let dashed = DASHED_NAMES.iter().find(|name| {
+                let end = start + name.len();
+                end <= chars.len()
+                    && chars[start..end].iter().collect::<String>().to_lowercase() == **name
+                    && !chars.get(end).is_some_and(is_ident_char)
+            });
+            if let Some(name) = dashed {
+                i += name.len();
+            }
+            while i < chars.len() {
+                if is_ident_char(&chars[i]) {
+                    i += 1;
+                } else if chars[i] == '.' && chars.get(i + 1).is_some_and(|c| c.is_alphabetic()) {
+                    // Member access, such as c1.cost
+                    i += 2;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err("Unterminated string literal".to_owned());
+            }
+            tokens.push(Token::Str(chars[start..i].iter().collect()));
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '[' {
+            tokens.push(Token::LBracket);
+            i += 1;
+        } else if c == ']' {
+            tokens.push(Token::RBracket);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) if *op != "=" => {
+                    tokens.push(Token::Op(op));
+                    i += op.len();
+                }
+                _ => return Err(format!("Unexpected character '{c}'"))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None
+        }
+    }
+
+    fn binary_level(
+        &mut self,
+        ops: &[&str],
+        next: fn(&mut Self) -> Result<Expr, String>
+    ) -> Result<Expr, String> {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            let op = match op {
+                "||" => BinaryOp::Or,
+                "&&" => BinaryOp::And,
+                "==" | "===" => BinaryOp::Eq,
+                "!=" | "!==" => BinaryOp::Ne,
+                "<" => BinaryOp::Lt,
+                "<=" => BinaryOp::Le,
+                ">" => BinaryOp::Gt,
+                ">=" => BinaryOp::Ge,
+                "+" => BinaryOp::Add,
+                "-" => BinaryOp::Sub,
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn expect(&mut self, expected: Token, text: &str) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(format!("Expected '{text}'"))
+        }
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr, String> {
+        let cond = self.parse_or()?;
+        if self.peek_op(&["?"]).is_none() {
+            return Ok(cond);
+        }
+        self.pos += 1;
+        let then = self.parse_conditional()?;
+        self.expect(Token::Op(":"), ":")?;
+        let otherwise = self.parse_conditional()?;
+        Ok(Expr::Conditional(Box::new(cond), Box::new(then), Box::new(otherwise)))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["||"], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["&&"], Self::parse_equality)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["==", "===", "!=", "!=="], Self::parse_comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["<", "<=", ">", ">="], Self::parse_additive)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["+", "-"], Self::parse_multiplicative)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        self.binary_level(&["*", "/", "%"], Self::parse_unary)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.peek_op(&["!", "-"]) {
+            Some(op) => {
+                self.pos += 1;
+                let operand = self.parse_unary()?;
+                let op = if op == "!" { UnaryOp::Not } else { UnaryOp::Neg };
+                Ok(Expr::Unary(op, Box::new(operand)))
+            },
+            None => self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Value(ExprValue::Number(n))),
+            Some(Token::Str(s)) => Ok(Expr::Value(ExprValue::Str(s))),
+            Some(Token::Ident(ident)) => {
+                let ident = normalize_ident(&ident);
+                match (ident.as_str(), self.peek()) {
+                    ("true", _) => Ok(Expr::Value(ExprValue::Bool(true))),
+                    ("false", _) => Ok(Expr::Value(ExprValue::Bool(false))),
+                    ("infinity" | "inf", _) => Ok(Expr::Value(ExprValue::Number(f64::INFINITY))),
+                    (_, Some(Token::LParen)) => {
+                        let function = Function::from_name(&ident)
+                            .ok_or_else(|| format!("Unknown function \"{ident}\""))?;
+                        self.pos += 1;
+                        let mut args = Vec::new();
+                        if self.peek() != Some(&Token::RParen) {
+                            loop {
+                                args.push(self.parse_conditional()?);
+                                if self.peek() != Some(&Token::Comma) { break; }
+                                self.pos += 1;
+                            }
+                        }
+                        self.expect(Token::RParen, ")")?;
+                        Ok(Expr::Call(function, args))
+                    },
+                    (_, Some(Token::LBracket)) => {
+                        self.pos += 1;
+                        let index = self.parse_conditional()?;
+                        self.expect(Token::RBracket, "]")?;
+                        Ok(Expr::Index(ident, Box::new(index)))
+                    },
+                    _ => Ok(Expr::Ident(ident))
+                }
+            },
+            Some(Token::LParen) => {
+                let expr = self.parse_conditional()?;
+                self.expect(Token::RParen, ")")?;
+                Ok(expr)
+            },
+            Some(token) => Err(format!("Unexpected token {token:?}")),
+            None => Err("Unexpected end of expression".to_owned())
+        }
+    }
+}
+
+/**
+ * Parses an expression
+ *
+ * An empty expression is parsed as `true`
+ */
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let tokens = tokenize(src).map_err(|err| format!("Error parsing \"{src}\": {err}"))?;
+    if tokens.is_empty() {
+        return Ok(Expr::Value(ExprValue::Bool(true)));
+    }
+
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_conditional().map_err(|err| format!("Error parsing \"{src}\": {err}"))?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("Error parsing \"{src}\": unexpected trailing tokens"));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /** Evaluates the expression with the given context */
+    pub fn eval(&self, ctx: &impl ExprContext) -> Result<ExprValue, String> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::Ident(name) => ctx.get(name).ok_or_else(|| format!("Unknown identifier \"{name}\"")),
+            Self::Index(name, index) => {
+                let index = index.eval(ctx)?.as_number()?;
+                if index < 0. || index.fract() != 0. {
+                    return Err(format!("Invalid index {index} for \"{name}\""));
+                }
+                ctx.get_index(name, index as usize).ok_or_else(|| format!("Unknown identifier \"{name}[{index}]\""))
+            },
+            Self::Call(function, args) => {
+                let args = args.iter()
+                    .map(|arg| arg.eval(ctx)?.as_number())
+                    .collect::<Result<Vec<f64>, String>>()?;
+                Ok(ExprValue::Number(function.call(&args)?))
+            },
+            Self::Conditional(cond, then, otherwise) => {
+                if cond.eval_bool(ctx)? { then.eval(ctx) } else { otherwise.eval(ctx) }
+            },
+            Self::Unary(op, operand) => {
+                let value = operand.eval(ctx)?;
+                match op {
+                    UnaryOp::Not => Ok(ExprValue::Bool(!value.is_truthy())),
+                    UnaryOp::Neg => Ok(ExprValue::Number(-value.as_number()?))
+                }
+            },
+            Self::Binary(BinaryOp::Or, lhs, rhs) => {
+                let lhs = lhs.eval(ctx)?;
+                if lhs.is_truthy() { Ok(lhs) } else { rhs.eval(ctx) }
+            },
+            Self::Binary(BinaryOp::And, lhs, rhs) => {
+                let lhs = lhs.eval(ctx)?;
+                if !lhs.is_truthy() { Ok(lhs) } else { rhs.eval(ctx) }
+            },
+            Self::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(ctx)?;
+                let rhs = rhs.eval(ctx)?;
+                match op {
+                    BinaryOp::Eq => Ok(ExprValue::Bool(lhs == rhs)),
+                    BinaryOp::Ne => Ok(ExprValue::Bool(lhs != rhs)),
+                    _ => {
+                        let (a, b) = (lhs.as_number()?, rhs.as_number()?);
+                        Ok(match op {
+                            BinaryOp::Lt => ExprValue::Bool(a < b),
+                            BinaryOp::Le => ExprValue::Bool(a <= b),
+                            BinaryOp::Gt => ExprValue::Bool(a > b),
+                            BinaryOp::Ge => ExprValue::Bool(a >= b),
+                            BinaryOp::Add => ExprValue::Number(a + b),
+                            BinaryOp::Sub => ExprValue::Number(a - b),
+                            BinaryOp::Mul => ExprValue::Number(a * b),
+                            BinaryOp::Div => ExprValue::Number(a / b),
+                            _ => ExprValue::Number(a % b)
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /** Evaluates the expression and returns its truthiness */
+    pub fn eval_bool(&self, ctx: &impl ExprContext) -> Result<bool, String> {
+        Ok(self.eval(ctx)?.is_truthy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct MapContext(HashMap<&'static str, ExprValue>);
+
+    impl ExprContext for MapContext {
+        fn get(&self, name: &str) -> Option<ExprValue> {
+            self.0.get(name).cloned()
+        }
+
+        fn get_index(&self, name: &str, index: usize) -> Option<ExprValue> {
+            match name {
+                "ms" => [0., 1., 2.].get(index).map(|n| ExprValue::Number(*n)),
+                _ => None
+            }
+        }
+    }
+
+    fn context() -> MapContext {
+        MapContext(HashMap::from([
+            ("very_active", ExprValue::Bool(false)),
+            ("active", ExprValue::Bool(true)),
+            ("semi_idle", ExprValue::Bool(false)),
+            ("idle", ExprValue::Bool(false)),
+            ("rho", ExprValue::Number(180.)),
+            ("laststrat", ExprValue::Str("T4C3d66".to_owned())),
+            ("c1.cost", ExprValue::Number(10.)),
+            ("c1.level", ExprValue::Number(23.)),
+            ("c2.cost", ExprValue::Number(12.)),
+            ("q1.cost", ExprValue::Number(11.)),
+            ("q2.cost", ExprValue::Number(13.))
+        ]))
+    }
+
+    fn eval(src: &str) -> bool {
+        parse(src).unwrap().eval_bool(&context()).unwrap()
+    }
+
+    #[test]
+    fn data_json_conditions() {
+        assert!(eval("Active || Very-Active"));
+        assert!(!eval("(Idle || Semi-Idle) && rho < 25 || rho < 25"));
+        assert!(eval("!Idle && rho >= 100"));
+        assert!(eval("(Active || Very-Active) && rho < 700 && rho >= 175 && (lastStrat !== \"T4C3d66\" || rho < 225)"));
+        assert!(!eval("(Active || Very-Active) && rho < 700 && lastStrat !== \"T4C3d66\""));
+        assert!(eval(""));
+        assert!(!eval("false"));
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        assert!(eval("rho - 80 * 2 == 20"));
+        assert!(eval("-(rho % 100) < -70"));
+    }
+
+    #[test]
+    fn strategy_conditions() {
+        assert!(eval("c1.cost + log10(6 + c1.level % 10) < min(q1.cost, q2.cost, ms[1] > 0 ? c2.cost : inf)"));
+        assert!(!eval("c1.cost + log10(6 + c1.level % 10) < min(q1.cost - 1, ms[0] > 0 ? 0 : c2.cost)"));
+        assert!(eval("max(1, 2) == 2 && floor(pow(2, 0.5) * 10) == 14 && (false ? 1 : true ? 2 : 3) == 2"));
+    }
+
+    #[test]
+    fn dashes() {
+        assert!(eval("c2.cost-c1.cost == 2 && rho-q1.cost == 169"));
+        assert!(eval("!semi-idle && !SEMI-IDLE && very-active-rho == -180"));
+        assert!(parse("semi-idleness").unwrap().eval(&context()).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("rho <").is_err());
+        assert!(parse("(rho < 5").is_err());
+        assert!(parse("rho = 5").is_err());
+        assert!(parse("\"unterminated").is_err());
+        assert!(parse("unknown").unwrap().eval(&context()).is_err());
+        assert!(parse("sqrt(2)").is_err());
+        assert!(parse("min(1, 2").is_err());
+        assert!(parse("rho ? 1").is_err());
+        assert!(parse("ms[5]").unwrap().eval(&context()).is_err());
+        assert!(parse("log10(1, 2)").unwrap().eval(&context()).is_err());
+    }
+}
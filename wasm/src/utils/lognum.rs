@@ -27,6 +27,11 @@ impl LogNum {
             sign: value.signum() as i8,
         }
     }
+
+    /** Creates a positive LogNum from its log10 value */
+    pub fn from_log10(value: f64) -> Self {
+        LogNum { value, sign: 1 }
+    }
 }
 
 impl From<f64> for LogNum {
@@ -188,14 +193,19 @@ impl DivAssign for LogNum {
 impl Rem for LogNum {
     type Output = LogNum;
 
-    fn rem(self, _rhs: Self) -> LogNum {
-        unimplemented!("Rem not implemented for LogNum")
+    /** Remainder with the sign of the dividend, NaN when the values do not fit in a f64 */
+    fn rem(self, rhs: Self) -> LogNum {
+        if self.value < rhs.value {
+            return self;
+        }
+        let (lhs, rhs) = (self.as_f64(), rhs.as_f64());
+        if lhs.is_finite() && rhs.is_finite() { LogNum::from_f64(lhs % rhs) } else { LogNum::nan() }
     }
 }
 
 impl RemAssign for LogNum {
-    fn rem_assign(&mut self, _rhs: Self) {
-        unimplemented!("RemAssign not implemented for LogNum")
+    fn rem_assign(&mut self, rhs: Self) {
+        *self = *self % rhs
     }
 }
 
@@ -219,6 +229,11 @@ impl LogNum {
     pub fn log10_f64(self) -> f64 {
         self.value
     }
+
+    /** Converts to f64, overflowing to infinity for large values */
+    pub fn as_f64(self) -> f64 {
+        10f64.powf(self.value) * self.sign as f64
+    }
 }
 
 impl Zero for LogNum {
@@ -580,4 +595,18 @@ impl Serialize for LogNum {
             S: serde::Serializer {
         serializer.serialize_f64(self.value)
     }
-}
\ No newline at end of file
+}
+
+/** LogNums are written as their log10 value */
+impl schemars::JsonSchema for LogNum {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "LogNum".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "number",
+            "description": "log10 of the value"
+        })
+    }
+}
@@ -14,7 +14,7 @@ impl Zero for LogNumPoly {
         LogNumPoly{coefficients: vec![]}
     }
     fn is_zero(&self) -> bool {
-        self.coefficients.len() == 0
+        self.coefficients.is_empty()
     }
 }
 
@@ -235,7 +235,7 @@ impl Mul<LogNum> for LogNumPoly {
             return LogNumPoly::zero();
         }
         for coeff in &mut self.coefficients {
-            *coeff = *coeff * rhs;
+            *coeff *= rhs;
         }
         self.cleanup();
         self
@@ -251,14 +251,12 @@ impl MulAssign<LogNum> for LogNumPoly {
     }
 }
 
+/** Like for LogNum, dividing by zero gives infinite coefficients instead of panicking */
 impl Div<LogNum> for LogNumPoly {
     type Output = LogNumPoly;
     fn div(mut self, rhs: LogNum) -> LogNumPoly {
-        if rhs.is_zero() {
-            panic!("division by zero");
-        }
         for coeff in &mut self.coefficients {
-            *coeff = *coeff / rhs;
+            *coeff /= rhs;
         }
         self.cleanup();
         self
@@ -267,9 +265,6 @@ impl Div<LogNum> for LogNumPoly {
 
 impl DivAssign<LogNum> for LogNumPoly {
     fn div_assign(&mut self, rhs: LogNum) {
-        if rhs.is_zero() {
-            panic!("division by zero");
-        }
         for coeff in &mut self.coefficients {
             *coeff /= rhs;
         }
@@ -352,4 +347,10 @@ mod tests {
             assert!(acceptable(p.eval_complex(r),Complex::zero()));
         }
     }
+
+    #[test]
+    fn poly_div_by_zero() {
+        let p = LogNumPoly::from_coeffs(vec![ONE, LogNum::from(2)]) / ZERO;
+        assert!(p.get_coefficient(1).log10_f64().is_infinite());
+    }
 }
\ No newline at end of file
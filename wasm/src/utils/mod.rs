@@ -8,8 +8,12 @@ pub mod value;
 pub mod variable;
 pub mod currency;
 
+pub mod expr;
+pub mod zeta;
+
 pub mod var_buy;
 pub mod settings;
+pub mod schedule;
 pub mod result;
 mod lognumpoly;
 
@@ -24,4 +28,22 @@ pub fn get_last_purchase(var_name: &str, var_buy_list: &[VarBuy]) -> Option<i32>
     }
 
     None
+}
+
+/**
+ * Returns the index `target` would have if inserted in `arr`
+ *
+ * `arr` must be sorted in increasing order with no repetitions
+ */
+pub fn binary_insertion_search(arr: &[f64], target: f64) -> i32 {
+    if arr.is_empty() || target < arr[0] {
+        return 0;
+    }
+    let mut l = 0;
+    let mut r = arr.len() - 1;
+    while l < r {
+        let m = (l + r).div_ceil(2);
+        if arr[m] <= target { l = m; } else { r = m - 1; }
+    }
+    l as i32 + 1
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::utils::{
@@ -7,7 +8,7 @@ use crate::utils::{
 use crate::api::config::TheoryType;
 
 /** Holds a result of a single simulation */
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct SimResult {
     pub theory: TheoryType,
     pub sigma: i64, // Type could change
@@ -18,6 +19,8 @@ pub struct SimResult {
     pub strat: String,
     pub tau_h: f64,
     pub time: f64,
+    /** Number of ticks simulated, including the ones after the publication */
+    pub ticks: u64,
     pub bought_vars: Vec<VarBuy>
 }
 
@@ -33,17 +36,39 @@ impl Default for SimResult {
             strat: "Result undefined".to_owned(),
             tau_h: 1.,
             time: 1.,
+            ticks: 0,
             bought_vars: Vec::new()
         }
     }
 }
 
+/**
+ * Returns the result with the highest tau/hr.
+ *
+ * If tau/hr are identical, the first result is returned.
+ */
+pub fn get_best_result(res1: SimResult, res2: SimResult) -> SimResult {
+    if res1.tau_h >= res2.tau_h { res1 } else { res2 }
+}
+
+/** Replaces `best` with `res` if there is no best result yet or if `res` is better */
+pub fn keep_best_result(best: &mut Option<SimResult>, res: SimResult) {
+    *best = Some(match best.take() {
+        Some(best) => get_best_result(best, res),
+        None => res
+    });
+}
+
 /** Holds a result for a SimAll simulation for one theory */
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct SimAllResult {
     pub theory: TheoryType,
     pub ratio: f64,
     pub last_pub: LogNum,
+    /** Total multiplier of the theory at the last publication */
+    pub multiplier: LogNum,
+    /** True for custom theories whose last publication reached the maximum tau */
+    pub completed: bool,
     pub active: SimResult,
     pub idle: SimResult
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+/*!
+ * Check-in schedules of players who only act when they open the game
+ */
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/**
+ * Times at which the player opens the game, repeating every `period`
+ *
+ * Checking in every 8 hours is `{"times": [0], "period": 28800}`, a weekly calendar lists the check-ins
+ * of a week with a period of 604800 seconds.
+ */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CheckInSchedule {
+    /** Check-in times (seconds) relative to the start of the period */
+    pub times: Vec<f64>,
+    /** Length of the period (seconds) */
+    pub period: f64,
+    /** Time of the period (seconds) at which the simulation starts */
+    #[serde(default)]
+    pub offset: f64,
+    /** If true, variables are also only bought at check-ins */
+    #[serde(default)]
+    pub buy_at_check_ins: bool
+}
+
+impl CheckInSchedule {
+    /** Checks that the schedule has check-ins within its period */
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.period.is_finite() && self.period > 0.) {
+            return Err("The check-in period must be positive".to_owned());
+        }
+        if self.times.is_empty() {
+            return Err("The check-in schedule has no check-in".to_owned());
+        }
+        if self.times.iter().any(|time| !(0. ..self.period).contains(time)) {
+            return Err("Check-in times must be within the check-in period".to_owned());
+        }
+        Ok(())
+    }
+
+    /** Returns the first check-in strictly after `t` (seconds since the start of the simulation) */
+    pub fn next_check_in(&self, t: f64) -> f64 {
+        let t = t + self.offset;
+        let period_start = (t / self.period).floor() * self.period;
+        let next = self.times.iter()
+            .map(|time| period_start + time)
+            .filter(|time| *time > t)
+            .fold(f64::INFINITY, f64::min);
+        let next = if next.is_finite() {
+            next
+        } else {
+            period_start + self.period + self.times.iter().copied().fold(f64::INFINITY, f64::min)
+        };
+        next - self.offset
+    }
+
+    /** Returns the schedule seen from a simulation starting `t` seconds later */
+    pub fn shifted(&self, t: f64) -> Self {
+        CheckInSchedule {
+            offset: (self.offset + t) % self.period,
+            ..self.clone()
+        }
+    }
+}
+
+/** Seconds in a day */
+const DAY: f64 = 86400.;
+
+/** Strategy category played during a daily time window */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ActivityWindow {
+    /** Start of the window (seconds since midnight) */
+    pub start: f64,
+    /** End of the window (seconds since midnight), the window wraps around midnight if it is before `start` */
+    pub end: f64,
+    /** Strategy category (Very-Active, Active, Semi-Idle, Idle) */
+    pub category: String
+}
+
+impl ActivityWindow {
+    fn contains(&self, time: f64) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&time)
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/**
+ * Daily activity of the player
+ *
+ * The player is offline outside of the windows.
+ */
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DailyProfile {
+    pub windows: Vec<ActivityWindow>,
+    /** Time of the day (seconds since midnight) at which the simulation starts */
+    #[serde(default)]
+    pub offset: f64
+}
+
+impl DailyProfile {
+    /** Checks that the windows are within a day */
+    pub fn validate(&self) -> Result<(), String> {
+        let in_day = |time: f64| (0. ..=DAY).contains(&time);
+        if !in_day(self.offset) {
+            return Err("The daily profile offset must be within a day".to_owned());
+        }
+        if self.windows.iter().any(|window| !in_day(window.start) || !in_day(window.end)) {
+            return Err("Activity windows must be within a day".to_owned());
+        }
+        Ok(())
+    }
+
+    /**
+     * Returns the category in force `t` seconds after the start of the simulation
+     *
+     * The first matching window is used, None means the player is offline.
+     */
+    pub fn category_at(&self, t: f64) -> Option<&str> {
+        let time = (t + self.offset).rem_euclid(DAY);
+        self.windows.iter()
+            .find(|window| window.contains(time))
+            .map(|window| window.category.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(times: Vec<f64>, period: f64, offset: f64) -> CheckInSchedule {
+        CheckInSchedule { times, period, offset, buy_at_check_ins: false }
+    }
+
+    #[test]
+    fn next_check_ins() {
+        let every_8h = schedule(vec![0.], 8. * 3600., 0.);
+        assert_eq!(every_8h.next_check_in(0.), 8. * 3600.);
+        assert_eq!(every_8h.next_check_in(8. * 3600.), 16. * 3600.);
+
+        let calendar = schedule(vec![3600., 7200.], 86400., 5000.);
+        assert_eq!(calendar.next_check_in(0.), 2200.);
+        assert_eq!(calendar.next_check_in(2200.), 86400. + 3600. - 5000.);
+        assert_eq!(calendar.shifted(2200.).next_check_in(0.), 86400. + 3600. - 7200.);
+    }
+
+    #[test]
+    fn invalid_schedules() {
+        assert!(schedule(vec![], 3600., 0.).validate().is_err());
+        assert!(schedule(vec![3600.], 3600., 0.).validate().is_err());
+        assert!(schedule(vec![0.], 0., 0.).validate().is_err());
+        assert!(schedule(vec![0., 1800.], 3600., 0.).validate().is_ok());
+    }
+
+    #[test]
+    fn daily_categories() {
+        let window = |start: f64, end: f64, category: &str| ActivityWindow {
+            start: start * 3600.,
+            end: end * 3600.,
+            category: category.to_owned()
+        };
+        let profile = DailyProfile {
+            windows: vec![window(8., 18., "Active"), window(22., 1., "Semi-Idle")],
+            offset: 12. * 3600.
+        };
+        assert!(profile.validate().is_ok());
+        assert_eq!(profile.category_at(0.), Some("Active"));
+        assert_eq!(profile.category_at(8. * 3600.), None);
+        assert_eq!(profile.category_at(12.5 * 3600.), Some("Semi-Idle"));
+        assert_eq!(profile.category_at(20. * 3600.), Some("Active"));
+        assert!(DailyProfile { windows: vec![window(8., 25., "Idle")], offset: 0. }.validate().is_err());
+    }
+}
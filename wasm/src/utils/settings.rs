@@ -2,10 +2,11 @@
  * Sim settings 
  */
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /** Possible user configurations for which strategies to simulate in "All" mode */
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SimAllStrats {
     All,
@@ -14,7 +15,7 @@ pub enum SimAllStrats {
 }
 
 /** Possible user configurations for how to sim completed CTs */
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum CompletedCTs {
     In,
@@ -23,7 +24,7 @@ pub enum CompletedCTs {
 }
 
 /** Holds sim settings */
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct SimSettings {
     pub dt: f64,
     pub ddt: f64,
@@ -31,6 +32,23 @@ pub struct SimSettings {
     pub bought_vars_delta: i32,
     pub sim_all_strats: SimAllStrats,
     pub completed_cts: CompletedCTs,
+    /** Shows the a2 and a3 levels in the strategies of EF, left to the EF sim */
     pub show_a23: bool,
     pub show_unofficials: bool
+}
+
+impl Default for SimSettings {
+    /** Default settings of the UI */
+    fn default() -> Self {
+        SimSettings {
+            dt: 1.5,
+            ddt: 1.0001,
+            mf_reset_depth: 0,
+            bought_vars_delta: 5,
+            sim_all_strats: SimAllStrats::All,
+            completed_cts: CompletedCTs::No,
+            show_a23: false,
+            show_unofficials: false
+        }
+    }
 }
\ No newline at end of file
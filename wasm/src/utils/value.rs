@@ -5,6 +5,8 @@
 use std::fmt::Debug;
 use num::Float;
 use dyn_clone::DynClone;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::utils::lognum::{self, LogNum};
 
@@ -15,9 +17,21 @@ pub trait ValueTrait: DynClone + Debug {
 
     /** Computes the value for the next level from the previous value*/
     fn compute_next(&self, old_value: LogNum, current_level: i32) -> LogNum;
+
+    /** Returns the model and its parameters */
+    fn describe(&self) -> ValueModel;
 }
 dyn_clone::clone_trait_object!(ValueTrait);
 
+/** Description of a value model and its parameters */
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValueModel {
+    Stepwise { base: LogNum, length: i32, offset: LogNum },
+    Exponential { base: LogNum },
+    Linear { slope: LogNum, offset: LogNum }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct StepwisePowerSumValue {
     base: LogNum,
@@ -47,6 +61,10 @@ impl ValueTrait for StepwisePowerSumValue {
     fn compute_next(&self, old_value: LogNum, current_level: i32) -> LogNum {
         old_value + self.base.powi(current_level / self.length)
     }
+
+    fn describe(&self) -> ValueModel {
+        ValueModel::Stepwise { base: self.base, length: self.length, offset: self.offset }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -70,6 +88,10 @@ impl ValueTrait for ExponentialValue {
     fn compute_next(&self, old_value: LogNum, _current_level: i32) -> LogNum {
         old_value * self.base
     }
+
+    fn describe(&self) -> ValueModel {
+        ValueModel::Exponential { base: self.base }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -95,6 +117,10 @@ impl ValueTrait for LinearValue {
     fn compute_next(&self, old_value: LogNum, _current_level: i32) -> LogNum {
         old_value + self.slope
     }
+
+    fn describe(&self) -> ValueModel {
+        ValueModel::Linear { slope: self.slope, offset: self.offset }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -140,4 +166,13 @@ impl Value {
             Self::Other(val) => val.compute_next(old_value, current_level),
         }
     }
+
+    pub fn describe(&self) -> ValueModel {
+        match self {
+            Self::Stepwise(val) => val.describe(),
+            Self::Exponential(val) => val.describe(),
+            Self::Linear(val) => val.describe(),
+            Self::Other(val) => val.describe(),
+        }
+    }
 }
\ No newline at end of file
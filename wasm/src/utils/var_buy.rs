@@ -1,8 +1,9 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 use crate::utils::lognum::LogNum;
 
 /** Holds a record of a variable purchase */
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct VarBuy {
     pub var_name: String,
     pub level: i32,
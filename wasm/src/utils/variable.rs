@@ -1,6 +1,6 @@
 use crate::utils::{
     lognum::{self, LogNum},
-    cost::Cost,
+    cost::{Cost, CostModel},
     value::Value,
     //currency::Currency
 };
@@ -32,6 +32,10 @@ impl Variable {
             value: lognum::ZERO
         };
         var.compute_from_zero();
+        // Variables with a free first level are bought right away
+        if matches!(var.cost_model.describe(), CostModel::FirstFree { .. }) {
+            var.buy();
+        }
 
         var
     }
@@ -54,4 +58,39 @@ impl Variable {
         self.level = level;
         self.compute_from_zero();
     }
-}
\ No newline at end of file
+
+    /** Returns the cost to go from (level) to (level+1) */
+    pub fn get_cost_for_level(&self, level: i32) -> LogNum {
+        self.cost_model.get_cost(level)
+    }
+
+    /** Returns the cost to go from (from) to (to), summed level by level, zero if (to) <= (from) */
+    pub fn get_cost_for_levels(&self, from: i32, to: i32) -> LogNum {
+        if to <= from {
+            return lognum::ZERO;
+        }
+        let mut total_cost = self.get_cost_for_level(from);
+        for level in (from + 1)..to {
+            total_cost += self.get_cost_for_level(level);
+        }
+        total_cost
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::cost::{ExponentialCost, FirstFreeCost};
+
+    #[test]
+    fn free_levels() {
+        let var = Variable::new("c1", Cost::new(FirstFreeCost::new(ExponentialCost::new(10., 2.))), Value::new_linear(1., 0.));
+        assert_eq!(var.level, 1);
+        let var = Variable::new("c2", Cost::new_constant(lognum::ZERO), Value::new_linear(1., 0.));
+        assert_eq!(var.level, 0);
+
+        let var = Variable::new("c3", Cost::new_exponential(10., 2.), Value::new_linear(1., 0.));
+        assert!((var.get_cost_for_levels(0, 2).log10_f64() - 30f64.log10()).abs() < 1e-12);
+        assert_eq!(var.get_cost_for_levels(2, 2), lognum::ZERO);
+        assert_eq!(var.get_cost_for_levels(3, 1), lognum::ZERO);
+    }
+}
@@ -0,0 +1,294 @@
+/*!
+ * Riemann zeta function on the critical line, evaluated with the Riemann-Siegel formula
+ *
+ * Mirrors the evaluation RZ does in `src/Theories/CTs/helpers/RZ.ts` for t >= 1.
+ */
+
+use std::f64::consts::PI;
+
+/** Step of the finite difference used for Z'(t), same as RZ */
+const DERIV_STEP: f64 = 1e-5;
+
+/** Coefficients of the Riemann-Siegel remainder terms C0 to C4, by increasing power of z */
+#[allow(clippy::excessive_precision)]
+const REMAINDER_COEFFS: [&[f64]; 5] = [
+    // Powers 0, 2, ...
+    &[
+        0.38268343236508977173,
+        0.43724046807752044936,
+        0.13237657548034352332,
+        -0.01360502604767418865,
+        -0.01356762197010358089,
+        -0.00162372532314446528,
+        0.00029705353733379691,
+        0.00007943300879521470,
+        0.00000046556124614505,
+        -0.00000143272516309551,
+        -0.00000010354847112313,
+        0.00000001235792708386,
+        0.00000000178810838580,
+        -0.00000000003391414390,
+        -0.00000000001632663390,
+        -0.00000000000037851093,
+        0.00000000000009327423,
+        0.00000000000000522184,
+        -0.00000000000000033507,
+        -0.00000000000000003412,
+        0.00000000000000000058,
+        0.00000000000000000015,
+    ],
+    // Powers 1, 3, ...
+    &[
+        -0.02682510262837534703,
+        0.01378477342635185305,
+        0.03849125048223508223,
+        0.00987106629906207647,
+        -0.00331075976085840433,
+        -0.00146478085779541508,
+        -0.00001320794062487696,
+        0.00005922748701847141,
+        0.00000598024258537345,
+        -0.00000096413224561698,
+        -0.00000018334733722714,
+        0.00000000446708756272,
+        0.00000000270963508218,
+        0.00000000007785288654,
+        -0.00000000002343762601,
+        -0.00000000000158301728,
+        0.00000000000012119942,
+        0.00000000000001458378,
+        -0.00000000000000028786,
+        -0.00000000000000008663,
+        -0.00000000000000000084,
+        0.00000000000000000036,
+        0.00000000000000000001,
+    ],
+    // Powers 0, 2, ...
+    &[
+        0.00518854283029316849,
+        0.00030946583880634746,
+        -0.01133594107822937338,
+        0.00223304574195814477,
+        0.00519663740886233021,
+        0.00034399144076208337,
+        -0.00059106484274705828,
+        -0.00010229972547935857,
+        0.00002088839221699276,
+        0.00000592766549309654,
+        -0.00000016423838362436,
+        -0.00000015161199700941,
+        -0.00000000590780369821,
+        0.00000000209115148595,
+        0.00000000017815649583,
+        -0.00000000001616407246,
+        -0.00000000000238069625,
+        0.00000000000005398265,
+        0.00000000000001975014,
+        0.00000000000000023333,
+        -0.00000000000000011188,
+        -0.00000000000000000416,
+        0.00000000000000000044,
+        0.00000000000000000003,
+    ],
+    // Powers 1, 3, ...
+    &[
+        -0.00133971609071945690,
+        0.00374421513637939370,
+        -0.00133031789193214681,
+        -0.00226546607654717871,
+        0.00095484999985067304,
+        0.00060100384589636039,
+        -0.00010128858286776622,
+        -0.00006865733449299826,
+        0.00000059853667915386,
+        0.00000333165985123995,
+        0.00000021919289102435,
+        -0.00000007890884245681,
+        -0.00000000941468508130,
+        0.00000000095701162109,
+        0.00000000018763137453,
+        -0.00000000000443783768,
+        -0.00000000000224267385,
+        -0.00000000000003627687,
+        0.00000000000001763981,
+        0.00000000000000079608,
+        -0.00000000000000009420,
+        -0.00000000000000000713,
+        0.00000000000000000033,
+        0.00000000000000000004,
+    ],
+    // Powers 0, 2, ...
+    &[
+        0.00046483389361763382,
+        -0.00100566073653404708,
+        0.00024044856573725793,
+        0.00102830861497023219,
+        -0.00076578610717556442,
+        -0.00020365286803084818,
+        0.00023212290491068728,
+        0.00003260214424386520,
+        -0.00002557906251794953,
+        -0.00000410746443891574,
+        0.00000117811136403713,
+        0.00000024456561422485,
+        -0.00000002391582476734,
+        -0.00000000750521420704,
+        0.00000000013312279416,
+        0.00000000013440626754,
+        0.00000000000351377004,
+        -0.00000000000151915445,
+        -0.00000000000008915418,
+        0.00000000000001119589,
+        0.00000000000000105160,
+        -0.00000000000000005179,
+        -0.00000000000000000807,
+        0.00000000000000000011,
+        0.00000000000000000004,
+    ],
+];
+
+/** Riemann-Siegel theta function (asymptotic expansion) */
+pub fn theta(t: f64) -> f64 {
+    t / 2. * (t / 2. / PI).ln() - t / 2. - PI / 8. + 1. / 48. / t + 7. / 5760. / t / t / t
+}
+
+/** Remainder term C`k`(z) of the Riemann-Siegel formula */
+fn remainder_term(k: usize, z: f64) -> f64 {
+    let z2 = z * z;
+    let start = if k.is_multiple_of(2) { 1. } else { z };
+    REMAINDER_COEFFS[k].iter()
+        .fold((0., start), |(sum, pow), coeff| (sum + coeff * pow, pow * z2))
+        .0
+}
+
+/**
+ * Hardy's Z function, real on the critical line with |Z(t)| = |zeta(1/2 + it)|
+ *
+ * `order` is the number of remainder terms used after the first one, RZ uses 2.
+ */
+pub fn riemann_siegel_z(t: f64, order: usize) -> f64 {
+    let full_n = (t / (2. * PI)).sqrt();
+    let n = full_n.floor();
+    let p = full_n - n;
+    let th = theta(t);
+
+    let mut z = 0.;
+    for j in 1..=(n as i64) {
+        let j = j as f64;
+        z += (th - t * j.ln()).cos() / j.sqrt();
+    }
+    z *= 2.;
+
+    let tpot = 2. * PI / t;
+    let mut r = 0.;
+    for k in 0..=order.min(REMAINDER_COEFFS.len() - 1) {
+        r += remainder_term(k, 2. * p - 1.) * tpot.powf(k as f64 * 0.5);
+    }
+    let sign = if (n as i64 - 1) % 2 == 0 { 1. } else { -1. };
+
+    z + r * sign * tpot.powf(0.25)
+}
+
+/** Z(t) with the precision used by RZ, defined for t >= 1 */
+pub fn z(t: f64) -> f64 {
+    riemann_siegel_z(t, 2)
+}
+
+/**
+ * Returns (re, im, Z) of zeta(1/2 + it) for t >= 1
+ *
+ * Same layout as the `ComplexValue` of RZ.
+ */
+pub fn zeta(t: f64) -> (f64, f64, f64) {
+    let z = z(t);
+    let th = theta(t);
+    (z * th.cos(), -z * th.sin(), z)
+}
+
+/** Modulus of the derivative of zeta along the critical line, computed like RZ does */
+pub fn zeta_deriv_norm(t: f64) -> f64 {
+    let (re, im, _) = zeta(t);
+    let (re2, im2, _) = zeta(t + DERIV_STEP);
+    ((re2 - re).powi(2) + (im2 - im).powi(2)).sqrt() / DERIV_STEP
+}
+
+/** Average spacing of the zeros of Z around `t` */
+pub fn zero_spacing(t: f64) -> f64 {
+    2. * PI / (t / (2. * PI)).ln().max(1.)
+}
+
+/** Refines a zero of Z in [a, b], where Z changes sign, with the Illinois method */
+pub fn refine_zero(mut a: f64, mut b: f64, tol: f64) -> f64 {
+    let mut fa = z(a);
+    let mut fb = z(b);
+    let mut side = 0;
+    let mut c = a;
+    for _ in 0..100 {
+        let prev = c;
+        c = (a * fb - b * fa) / (fb - fa);
+        let fc = z(c);
+        if fc == 0. || (c - prev).abs() < tol {
+            break;
+        }
+        if fc.signum() == fb.signum() {
+            b = c;
+            fb = fc;
+            if side == -1 { fa /= 2.; }
+            side = -1;
+        } else {
+            a = c;
+            fa = fc;
+            if side == 1 { fb /= 2.; }
+            side = 1;
+        }
+    }
+    c
+}
+
+/**
+ * Returns the zeros of Z in [`from`, `to`], in increasing order
+ *
+ * Zeros are bracketed by sign changes on a grid of `resolution` points per average zero spacing,
+ * so close pairs of zeros can be missed if `resolution` is too low.
+ */
+pub fn find_zeros(from: f64, to: f64, resolution: u32) -> Vec<f64> {
+    let mut zeros = Vec::new();
+    let mut a = from.max(1.);
+    let mut fa = z(a);
+    while a < to {
+        let b = (a + zero_spacing(a) / resolution.max(1) as f64).min(to);
+        let fb = z(b);
+        if fa == 0. {
+            zeros.push(a);
+        } else if fa.signum() != fb.signum() && fb != 0. {
+            zeros.push(refine_zero(a, b, 1e-10));
+        }
+        a = b;
+        fa = fb;
+    }
+    if fa == 0. {
+        zeros.push(a);
+    }
+    zeros
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_zeros() {
+        let zeros = find_zeros(10., 40., 8);
+        let expected = [14.134725, 21.022040, 25.010858, 30.424876, 32.935062, 37.586178];
+        assert_eq!(zeros.len(), expected.len());
+        for (zero, expected) in zeros.iter().zip(expected) {
+            assert!((zero - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn zero_count() {
+        // There are 649 zeros with 0 < t < 1000
+        assert_eq!(find_zeros(1., 1000., 8).len(), 649);
+    }
+}